@@ -0,0 +1,323 @@
+// Rust Codex32 Library and Reference Implementation
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! codex32 Python Bindings
+//!
+//! A `pyo3` extension module, named `codex32`, exposing codex32 strings,
+//! field elements, the checksum engine, interpolation and error correction
+//! to Python, for generating test vectors and for research. Build and test
+//! it with
+//!
+//! ```text
+//! pip install maturin pytest
+//! maturin develop
+//! pytest
+//! ```
+//!
+//! Errors are raised as `codex32.Codex32Error`, a subclass of `ValueError`,
+//! whose message is the `Debug` output of the library error.
+//!
+
+use codex32::{ChecksumEngine, Codex32String, Fe};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyValueError, PyZeroDivisionError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+create_exception!(
+    codex32,
+    Codex32Error,
+    PyValueError,
+    "An error from the codex32 library"
+);
+
+/// Converts a library error to a Python exception
+fn py_error(e: codex32::Error) -> PyErr {
+    Codex32Error::new_err(format!("{:?}", e))
+}
+
+/// An element of GF32, written as a single bech32 character
+#[pyclass(name = "Fe", module = "codex32", frozen, eq, hash)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+struct PyFe(Fe);
+
+#[pymethods]
+impl PyFe {
+    /// Creates a field element from a single bech32 character, in either case
+    #[new]
+    fn new(ch: &str) -> PyResult<PyFe> {
+        ch.parse().map(PyFe).map_err(py_error)
+    }
+
+    /// Creates a field element from its numeric value, 0 through 31
+    #[staticmethod]
+    fn from_int(i: u8) -> PyResult<PyFe> {
+        Fe::from_u8(i).map(PyFe).map_err(py_error)
+    }
+
+    /// All field elements, in alphabetical order
+    #[staticmethod]
+    fn iter_alpha() -> Vec<PyFe> {
+        Fe::iter_alpha().map(PyFe).collect()
+    }
+
+    /// The numeric value of the field element
+    // pyo3 methods cannot take `self` by value
+    #[allow(clippy::wrong_self_convention)]
+    fn to_int(&self) -> u8 {
+        self.0.to_u8()
+    }
+
+    fn __int__(&self) -> u8 {
+        self.0.to_u8()
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_char().into()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Fe('{}')", self.0.to_char())
+    }
+
+    fn __add__(&self, other: PyFe) -> PyFe {
+        PyFe(self.0 + other.0)
+    }
+
+    fn __sub__(&self, other: PyFe) -> PyFe {
+        PyFe(self.0 - other.0)
+    }
+
+    fn __mul__(&self, other: PyFe) -> PyFe {
+        PyFe(self.0 * other.0)
+    }
+
+    fn __truediv__(&self, other: PyFe) -> PyResult<PyFe> {
+        if other.0 == Fe::Q {
+            return Err(PyZeroDivisionError::new_err("division by zero in GF32"));
+        }
+        Ok(PyFe(self.0 / other.0))
+    }
+}
+
+/// A codex32 string, containing a valid checksum
+///
+/// Its `repr` shows only the header. To get at the full string, use
+/// `expose_secret`.
+#[pyclass(name = "Codex32String", module = "codex32", frozen, eq)]
+#[derive(PartialEq)]
+struct PyCodex32String(Codex32String);
+
+#[pymethods]
+impl PyCodex32String {
+    /// Parses an already-checksummed string
+    #[new]
+    fn new(s: String) -> PyResult<PyCodex32String> {
+        Codex32String::from_string(s)
+            .map(PyCodex32String)
+            .map_err(py_error)
+    }
+
+    /// Appends a checksum to a not-yet-checksummed string
+    #[staticmethod]
+    fn from_unchecksummed_string(s: String) -> PyResult<PyCodex32String> {
+        Codex32String::from_unchecksummed_string(s)
+            .map(PyCodex32String)
+            .map_err(py_error)
+    }
+
+    /// Encodes seed data as a codex32 string with the given header
+    #[staticmethod]
+    fn from_seed(
+        hrp: &str,
+        threshold: usize,
+        id: &str,
+        share_index: PyFe,
+        data: &[u8],
+    ) -> PyResult<PyCodex32String> {
+        Codex32String::from_seed(hrp, threshold, id, share_index.0, data)
+            .map(PyCodex32String)
+            .map_err(py_error)
+    }
+
+    /// Parses a string, correcting a single substituted character if
+    /// necessary, and returns it along with the position of the corrected
+    /// character, or `None`
+    #[staticmethod]
+    fn correct(s: String) -> PyResult<(PyCodex32String, Option<usize>)> {
+        Codex32String::from_string_corrected(s)
+            .map(|(s, pos)| (PyCodex32String(s), pos))
+            .map_err(py_error)
+    }
+
+    /// Interpolates a list of shares to derive the share at `target`
+    ///
+    /// Using the target `Fe('s')` recovers the master seed.
+    #[staticmethod]
+    fn interpolate_at(
+        shares: Vec<PyRef<'_, PyCodex32String>>,
+        target: PyFe,
+    ) -> PyResult<PyCodex32String> {
+        let shares: Vec<Codex32String> = shares.iter().map(|s| s.0.clone()).collect();
+        Codex32String::interpolate_at(&shares, target.0)
+            .map(PyCodex32String)
+            .map_err(py_error)
+    }
+
+    /// Splits a master seed into `n_shares` shares, deterministically
+    #[staticmethod]
+    #[pyo3(signature = (hrp, threshold, id, seed, n_shares, extra_entropy = None))]
+    fn split_deterministic(
+        hrp: &str,
+        threshold: usize,
+        id: &str,
+        seed: &[u8],
+        n_shares: usize,
+        extra_entropy: Option<&[u8]>,
+    ) -> PyResult<Vec<PyCodex32String>> {
+        let extra_entropy = extra_entropy.unwrap_or(&[]);
+        Codex32String::split_deterministic(hrp, threshold, id, seed, extra_entropy, n_shares)
+            .map(|shares| shares.into_iter().map(PyCodex32String).collect())
+            .map_err(py_error)
+    }
+
+    /// The human-readable part
+    #[getter]
+    fn hrp(&self) -> &str {
+        self.0.parts().hrp()
+    }
+
+    /// The threshold, with 0 meaning the string is an unshared secret
+    #[getter]
+    fn threshold(&self) -> usize {
+        self.0.parts().threshold()
+    }
+
+    /// The four-character identifier
+    #[getter]
+    fn id(&self) -> &str {
+        self.0.parts().id()
+    }
+
+    /// The share index
+    #[getter]
+    fn share_index(&self) -> PyFe {
+        PyFe(self.0.parts().share_index())
+    }
+
+    /// The checksum characters
+    #[getter]
+    fn checksum(&self) -> &str {
+        self.0.parts().checksum()
+    }
+
+    /// The payload as bytes, which for an `s` share is the master seed
+    fn data<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.0.parts().data())
+    }
+
+    /// The full string, including the (possibly secret) payload
+    fn expose_secret(&self) -> &str {
+        self.0.expose_secret()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+/// The codex32 checksum engine, which consumes one character at a time
+#[pyclass(name = "ChecksumEngine", module = "codex32")]
+#[derive(Clone)]
+struct PyChecksumEngine(ChecksumEngine);
+
+#[pymethods]
+impl PyChecksumEngine {
+    /// Creates an engine for the normal codex32 checksum
+    #[staticmethod]
+    fn new_codex32_short() -> PyChecksumEngine {
+        PyChecksumEngine(ChecksumEngine::new_codex32_short())
+    }
+
+    /// Creates an engine for the "long" codex32 checksum
+    #[staticmethod]
+    fn new_codex32_long() -> PyChecksumEngine {
+        PyChecksumEngine(ChecksumEngine::new_codex32_long())
+    }
+
+    /// Loads a human-readable part into the engine
+    fn input_hrp(&mut self, hrp: &str) -> PyResult<()> {
+        self.0.input_hrp(hrp).map_err(py_error)
+    }
+
+    /// Adds a single data character
+    fn input_char(&mut self, ch: char) -> PyResult<()> {
+        self.0.input_char(ch).map_err(py_error)
+    }
+
+    /// Adds a string of data characters
+    fn input_data_str(&mut self, s: &str) -> PyResult<()> {
+        self.0.input_data_str(s).map_err(py_error)
+    }
+
+    /// Adds a single field element
+    fn input_fe(&mut self, fe: PyFe) {
+        self.0.input_fe(fe.0);
+    }
+
+    /// Adds the target residue, after which the residue is the checksum
+    fn input_own_target(&mut self) {
+        self.0.input_own_target();
+    }
+
+    /// The current residue
+    fn residue(&self) -> Vec<PyFe> {
+        self.0.residue().iter().copied().map(PyFe).collect()
+    }
+
+    /// The residue of a valid string
+    fn target(&self) -> Vec<PyFe> {
+        self.0.target().iter().copied().map(PyFe).collect()
+    }
+
+    /// Whether the input so far has a valid checksum
+    fn is_valid(&self) -> bool {
+        self.0.is_valid()
+    }
+
+    fn __copy__(&self) -> PyChecksumEngine {
+        self.clone()
+    }
+}
+
+/// The Lagrange coefficients for interpolating shares with the given
+/// indices at `target`
+#[pyfunction]
+fn lagrange_coefficients(indices: Vec<PyFe>, target: PyFe) -> PyResult<Vec<PyFe>> {
+    let indices: Vec<Fe> = indices.into_iter().map(|fe| fe.0).collect();
+    codex32::lagrange_coefficients(&indices, target.0)
+        .map(|coeffs| coeffs.into_iter().map(PyFe).collect())
+        .map_err(py_error)
+}
+
+#[pymodule]
+#[pyo3(name = "codex32")]
+fn codex32_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyFe>()?;
+    m.add_class::<PyCodex32String>()?;
+    m.add_class::<PyChecksumEngine>()?;
+    m.add_function(wrap_pyfunction!(lagrange_coefficients, m)?)?;
+    m.add("Codex32Error", m.py().get_type::<Codex32Error>())?;
+    Ok(())
+}
@@ -0,0 +1,174 @@
+# Rust Codex32 Library and Reference Implementation
+#
+# To the extent possible under law, the author(s) have dedicated all
+# copyright and related and neighboring rights to this software to
+# the public domain worldwide. This software is distributed without
+# any warranty.
+#
+# You should have received a copy of the CC0 Public Domain Dedication
+# along with this software.
+# If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+
+"""Tests of the Python bindings against the BIP-93 test vectors."""
+
+import pytest
+
+from codex32 import ChecksumEngine, Codex32Error, Codex32String, Fe, lagrange_coefficients
+
+
+def test_vector_1():
+    secret = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw"
+    c32 = Codex32String(secret)
+    assert c32.hrp == "ms"
+    assert c32.threshold == 0
+    assert c32.id == "test"
+    assert c32.share_index == Fe("s")
+    assert c32.checksum == "4nzvca9cmczlw"
+    assert c32.data().hex() == "318c6318c6318c6318c6318c6318c631"
+    assert c32.expose_secret() == secret
+    assert "xxxx" not in repr(c32)
+
+
+def test_vector_2():
+    shares = [
+        Codex32String("MS12NAMEA320ZYXWVUTSRQPNMLKJHGFEDCAXRPP870HKKQRM"),
+        Codex32String("MS12NAMECACDEFGHJKLMNPQRSTUVWXYZ023FTR2GDZMPY6PN"),
+    ]
+    share_d = Codex32String.interpolate_at(shares, Fe("d"))
+    assert share_d.expose_secret() == "MS12NAMEDLL4F8JLH4E5VDVULDLFXU2JHDNLSM97XVENRXEG"
+    seed = Codex32String.interpolate_at(shares, Fe("s"))
+    assert seed.expose_secret() == "MS12NAMES6XQGUZTTXKEQNJSJZV4JV3NZ5K3KWGSPHUH6EVW"
+    assert seed.data().hex() == "d1808e096b35b209ca12132b264662a5"
+
+
+def test_vector_3():
+    shares = [
+        Codex32String("ms13cashsllhdmn9m42vcsamx24zrxgs3qqjzqud4m0d6nln"),
+        Codex32String("ms13casha320zyxwvutsrqpnmlkjhgfedca2a8d0zehn8a0t"),
+        Codex32String("ms13cashcacdefghjklmnpqrstuvwxyz023949xq35my48dr"),
+    ]
+    expected = {
+        "d": "ms13cashd0wsedstcdcts64cd7wvy4m90lm28w4ffupqs7rm",
+        "e": "ms13casheekgpemxzshcrmqhaydlp6yhms3ws7320xyxsar9",
+        "f": "ms13cashf8jh6sdrkpyrsp5ut94pj8ktehhw2hfvyrj48704",
+    }
+    for index, share in expected.items():
+        assert Codex32String.interpolate_at(shares, Fe(index)).expose_secret() == share
+    assert shares[0].data().hex() == "ffeeddccbbaa99887766554433221100"
+
+
+def test_vector_4():
+    seed = bytes.fromhex("ffeeddccbbaa99887766554433221100ffeeddccbbaa99887766554433221100")
+    c32 = Codex32String.from_seed("ms", 0, "leet", Fe("s"), seed)
+    assert (
+        c32.expose_secret()
+        == "ms10leetsllhdmn9m42vcsamx24zrxgs3qrl7ahwvhw4fnzrhve25gvezzyqqtum9pgv99ycma"
+    )
+    assert c32.data() == seed
+    alt = Codex32String("ms10leetsllhdmn9m42vcsamx24zrxgs3qrl7ahwvhw4fnzrhve25gvezzyqpj82dp34u6lqtd")
+    assert alt.data() == seed
+
+
+def test_vector_5():
+    c32 = Codex32String(
+        "MS100C8VSM32ZXFGUHPCHTLUPZRY9X8GF2TVDW0S3JN54KHCE6MUA7LQPZYGSFJD6AN074RXVCEMLH8WU3TK925ACDEFGHJKLMNPQRSTUVWXY06FHPV80UNDVARHRAK"
+    )
+    assert c32.data().hex() == (
+        "dc5423251cb87175ff8110c8531d0952d8d73e1194e95b5f19d6f9df7c01111"
+        "104c9baecdfea8cccc677fb9ddc8aec5553b86e528bcadfdcc201c17c638c47e9"
+    )
+
+
+@pytest.mark.parametrize(
+    "invalid",
+    [
+        # Bad checksums
+        "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxxxxmazxdp4sx5q",
+        "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxxxq70v3y94304t",
+        # Improper lengths
+        "ms10testsxxxxxxxxxxxxxxxxxxxxxxxx8ty2gx0n6rnaa",
+        "ms12testxxxxxxxxxxxxxxxxxxxxxxxxxtn5jkk94ayuqc",
+        # Invalid share index and threshold
+        "ms10testxxxxxxxxxxxxxxxxxxxxxxxxxxxx3wq9mzgrwag9",
+        "ms1testxxxxxxxxxxxxxxxxxxxxxxxxxxxxs9lz3we7s9wh4",
+        # Mixed case
+        "MS10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw",
+        "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4NZVCA9CMCZLW",
+    ],
+)
+def test_invalid(invalid):
+    with pytest.raises(Codex32Error):
+        Codex32String(invalid)
+
+
+def test_unchecksummed():
+    c32 = Codex32String.from_unchecksummed_string("ms13cashsllhdmn9m42vcsamx24zrxgs3qq")
+    assert c32.expose_secret() == "ms13cashsllhdmn9m42vcsamx24zrxgs3qqjzqud4m0d6nln"
+
+
+def test_correct():
+    valid = "ms13casha320zyxwvutsrqpnmlkjhgfedca2a8d0zehn8a0t"
+    assert Codex32String.correct(valid) == (Codex32String(valid), None)
+    typo = valid[:20] + "q" + valid[21:]
+    fixed, pos = Codex32String.correct(typo)
+    assert fixed.expose_secret() == valid
+    assert pos == 20
+    with pytest.raises(Codex32Error):
+        Codex32String.correct(valid[:20] + "pp" + valid[22:])
+
+
+def test_split():
+    seed = bytes(range(16))
+    shares = Codex32String.split_deterministic("ms", 3, "leet", seed, 5)
+    assert [str(s.share_index) for s in shares] == ["a", "c", "d", "e", "f"]
+    secret = Codex32String.interpolate_at(shares[2:], Fe("s"))
+    assert secret == Codex32String.from_seed("ms", 3, "leet", Fe("s"), seed)
+    other = Codex32String.split_deterministic("ms", 3, "leet", seed, 5, extra_entropy=b"x")
+    assert other[0] != shares[0]
+
+
+def test_field():
+    assert [str(fe) for fe in Fe.iter_alpha()][:4] == ["a", "c", "d", "e"]
+    assert Fe.from_int(0) == Fe("q")
+    assert int(Fe("p")) == 1
+    assert Fe("Z").to_int() == 2
+    assert repr(Fe("x")) == "Fe('x')"
+    for a in Fe.iter_alpha():
+        assert a + a == Fe("q")
+        if a != Fe("q"):
+            assert a * (Fe("p") / a) == Fe("p")
+    with pytest.raises(ZeroDivisionError):
+        Fe("p") / Fe("q")
+    with pytest.raises(Codex32Error):
+        Fe("b")
+    assert len({Fe("a"), Fe("A"), Fe("c")}) == 2
+
+
+def test_checksum_engine():
+    engine = ChecksumEngine.new_codex32_short()
+    engine.input_hrp("ms")
+    engine.input_data_str("0testsxxxxxxxxxxxxxxxxxxxxxxxxxx")
+    copy = engine.__copy__()
+    engine.input_own_target()
+    assert "".join(str(fe) for fe in engine.residue()) == "4nzvca9cmczlw"
+
+    copy.input_data_str("4nzvca9cmczlw")
+    assert copy.is_valid()
+    assert copy.residue() == copy.target()
+
+
+def test_lagrange_coefficients():
+    shares = [
+        Codex32String("ms13cashsllhdmn9m42vcsamx24zrxgs3qqjzqud4m0d6nln"),
+        Codex32String("ms13casha320zyxwvutsrqpnmlkjhgfedca2a8d0zehn8a0t"),
+        Codex32String("ms13cashcacdefghjklmnpqrstuvwxyz023949xq35my48dr"),
+    ]
+    indices = [s.share_index for s in shares]
+    coeffs = lagrange_coefficients(indices, Fe("s"))
+    assert coeffs == [Fe("p"), Fe("q"), Fe("q")]
+    total = Fe("q")
+    for coeff in lagrange_coefficients(indices, Fe("d")):
+        total = total + coeff
+    assert total == Fe("p")
+    with pytest.raises(Codex32Error):
+        lagrange_coefficients([Fe("a"), Fe("a")], Fe("s"))
@@ -0,0 +1,65 @@
+#!/usr/bin/env python3
+#
+# Rust Codex32 Library and Reference Implementation
+#
+# To the extent possible under law, the author(s) have dedicated all
+# copyright and related and neighboring rights to this software to
+# the public domain worldwide. This software is distributed without
+# any warranty.
+#
+# You should have received a copy of the CC0 Public Domain Dedication
+# along with this software.
+# If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+
+"""Answers the requests of the `conformance` harness using the Python bindings.
+
+This is mostly a template for adapters to other implementations, which
+only need to replace the three functions below, and is run by
+`contrib/test.sh` as a check of the bindings and of the harness itself:
+
+    cargo run --bin conformance -- python3 contrib/conformance.py
+"""
+
+import sys
+
+from codex32 import Codex32Error, Codex32String, Fe
+
+
+def validate(s):
+    Codex32String(s)
+    return None
+
+
+def interpolate(target, *shares):
+    shares = [Codex32String(s) for s in shares]
+    return Codex32String.interpolate_at(shares, Fe(target)).expose_secret()
+
+
+def correct(s):
+    corrected, _ = Codex32String.correct(s)
+    return corrected.expose_secret()
+
+
+COMMANDS = {
+    "validate": validate,
+    "interpolate": interpolate,
+    "correct": correct,
+}
+
+
+def main():
+    for line in sys.stdin:
+        command, *args = line.split() or [""]
+        if command not in COMMANDS:
+            print("unsupported", flush=True)
+            continue
+        try:
+            result = COMMANDS[command](*args)
+        except (Codex32Error, ValueError, TypeError) as e:
+            print("error", str(e).replace("\n", " "), flush=True)
+            continue
+        print("ok" if result is None else "ok " + result, flush=True)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,147 @@
+/*
+ * Rust Codex32 Library and Reference Implementation
+ *
+ * To the extent possible under law, the author(s) have dedicated all
+ * copyright and related and neighboring rights to this software to
+ * the public domain worldwide. This software is distributed without
+ * any warranty.
+ *
+ * You should have received a copy of the CC0 Public Domain Dedication
+ * along with this software.
+ * If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+ */
+
+/*
+ * Test of the C bindings. Run from the root of the repository with
+ *
+ *     cargo rustc --lib --features ffi --crate-type staticlib
+ *     cc -std=c99 -Wall -Wextra -Werror -Iinclude contrib/ffi-test.c \
+ *         target/debug/libcodex32.a -lpthread -ldl -lm -o target/ffi-test
+ *     target/ffi-test
+ */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "codex32.h"
+
+static int failures = 0;
+
+#define CHECK(cond)                                                    \
+    do {                                                               \
+        if (!(cond)) {                                                 \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,     \
+                    __LINE__, #cond);                                  \
+            failures++;                                                \
+        }                                                              \
+    } while (0)
+
+/* BIP-93 test vector 1 */
+static const char *VECTOR_1 = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw";
+
+static void test_parse(void) {
+    codex32_string *s = NULL;
+    char buf[128];
+    uint8_t data[64];
+    size_t len = 0;
+
+    CHECK(codex32_validate(VECTOR_1) == CODEX32_ERROR_OK);
+    CHECK(codex32_validate("ms10test") == CODEX32_ERROR_INVALID_LENGTH);
+    CHECK(codex32_validate(NULL) == CODEX32_ERROR_NULL_POINTER);
+
+    CHECK(codex32_parse(VECTOR_1, &s) == CODEX32_ERROR_OK);
+    CHECK(codex32_string_len(s) == strlen(VECTOR_1));
+    CHECK(codex32_string_copy(s, buf, strlen(VECTOR_1)) == CODEX32_ERROR_BUFFER_TOO_SMALL);
+    CHECK(codex32_string_copy(s, buf, sizeof buf) == CODEX32_ERROR_OK);
+    CHECK(strcmp(buf, VECTOR_1) == 0);
+    CHECK(codex32_string_data(s, data, sizeof data, &len) == CODEX32_ERROR_OK);
+    CHECK(len == 16);
+    CHECK(data[0] == 0x31 && data[1] == 0x8c && data[15] == 0x31);
+    codex32_wipe((uint8_t *)buf, sizeof buf);
+    codex32_wipe(data, sizeof data);
+    codex32_string_free(s);
+}
+
+static void test_correct(void) {
+    codex32_string *s = NULL;
+    char buf[128];
+    size_t pos = 0;
+
+    strcpy(buf, VECTOR_1);
+    buf[20] = 'q';
+    CHECK(codex32_validate(buf) == CODEX32_ERROR_INVALID_CHECKSUM);
+    CHECK(codex32_correct(buf, &s, &pos) == CODEX32_ERROR_OK);
+    CHECK(pos == 20);
+    CHECK(codex32_string_copy(s, buf, sizeof buf) == CODEX32_ERROR_OK);
+    CHECK(strcmp(buf, VECTOR_1) == 0);
+    codex32_string_free(s);
+
+    CHECK(codex32_correct(VECTOR_1, &s, &pos) == CODEX32_ERROR_OK);
+    CHECK(pos == SIZE_MAX);
+    codex32_string_free(s);
+
+    buf[20] = 'q';
+    buf[21] = 'q';
+    CHECK(codex32_correct(buf, &s, &pos) == CODEX32_ERROR_UNCORRECTABLE);
+}
+
+static void test_split_recover(void) {
+    uint8_t seed[32];
+    uint8_t recovered[64];
+    size_t recovered_len = 0;
+    codex32_string *shares[5] = { NULL };
+    const codex32_string *subset[3];
+    codex32_string *secret = NULL;
+    codex32_string *interpolated = NULL;
+    char a[128], b[128];
+    size_t i;
+
+    for (i = 0; i < sizeof seed; i++) {
+        seed[i] = (uint8_t)i;
+    }
+    CHECK(codex32_split("ms", 3, "cxxx", seed, sizeof seed, NULL, 0, 5, shares)
+          == CODEX32_ERROR_OK);
+    CHECK(codex32_split("ms", 1, "cxxx", seed, sizeof seed, NULL, 0, 5, shares)
+          == CODEX32_ERROR_INVALID_THRESHOLD_N);
+
+    subset[0] = shares[4];
+    subset[1] = shares[1];
+    subset[2] = shares[2];
+    CHECK(codex32_recover_seed(subset, 3, recovered, sizeof recovered, &recovered_len)
+          == CODEX32_ERROR_OK);
+    CHECK(recovered_len == sizeof seed);
+    CHECK(memcmp(recovered, seed, sizeof seed) == 0);
+    CHECK(codex32_recover_seed(subset, 2, recovered, sizeof recovered, &recovered_len)
+          == CODEX32_ERROR_THRESHOLD_NOT_PASSED);
+    CHECK(codex32_recover_seed(subset, 3, recovered, 16, &recovered_len)
+          == CODEX32_ERROR_BUFFER_TOO_SMALL);
+
+    CHECK(codex32_from_seed("ms", 3, "cxxx", 's', seed, sizeof seed, &secret)
+          == CODEX32_ERROR_OK);
+    CHECK(codex32_interpolate(subset, 3, 's', &interpolated) == CODEX32_ERROR_OK);
+    CHECK(codex32_string_copy(secret, a, sizeof a) == CODEX32_ERROR_OK);
+    CHECK(codex32_string_copy(interpolated, b, sizeof b) == CODEX32_ERROR_OK);
+    CHECK(strcmp(a, b) == 0);
+    CHECK(codex32_interpolate(subset, 3, 'b', &interpolated) == CODEX32_ERROR_INVALID_CHAR);
+
+    codex32_wipe(recovered, sizeof recovered);
+    codex32_wipe((uint8_t *)a, sizeof a);
+    codex32_wipe((uint8_t *)b, sizeof b);
+    codex32_string_free(secret);
+    codex32_string_free(interpolated);
+    for (i = 0; i < 5; i++) {
+        codex32_string_free(shares[i]);
+    }
+}
+
+int main(void) {
+    test_parse();
+    test_correct();
+    test_split_recover();
+    if (failures > 0) {
+        fprintf(stderr, "%d checks failed\n", failures);
+        return 1;
+    }
+    printf("All checks passed\n");
+    return 0;
+}
@@ -10,12 +10,31 @@ NIGHTLY=false
 if cargo --version | grep nightly; then
     NIGHTLY=true
 fi
+MSRV=false
+if cargo --version | grep "1\.48"; then
+    MSRV=true
+fi
 
 build_and_test () {
     cargo build --no-default-features --features="$1"
     cargo test --no-default-features --features="$1"
 }
 
+# Pin dependencies whose later versions need a newer compiler than our MSRV.
+if [ "$MSRV" = true ]; then
+    # Used by proptest
+    cargo update -p byteorder --precise 1.4.3
+    cargo update -p libc --precise 0.2.164
+    cargo update -p num-traits --precise 0.2.18
+    cargo update -p ppv-lite86 --precise 0.2.17
+    # Used by serde_json and toml
+    cargo update -p serde --precise 1.0.219
+    cargo update -p itoa --precise 1.0.15
+    cargo update -p ryu --precise 1.0.20
+    # Used by secp256k1, to build libsecp256k1
+    cargo update -p cc --precise 1.0.83
+fi
+
 # Sanity, check tools exist.
 cargo --version
 rustc --version
@@ -39,7 +58,84 @@ fi
 if [ "${DO_FEATURE_MATRIX-false}" = true ]; then
     # No features
     build_and_test ""
-    # Feature combos (currently no features or dependncies in library)
+    build_and_test "std"
+    # Feature combos
+    build_and_test "zeroize"
+    build_and_test "rand_core"
+    build_and_test "zeroize rand_core"
+    build_and_test "secp256k1"
+    build_and_test "std zeroize rand_core secp256k1"
+    build_and_test "ffi"
+    # wasm-bindgen itself needs a far newer compiler than our MSRV
+    if [ "$MSRV" = false ]; then
+        build_and_test "wasm-bindgen zeroize"
+    fi
+fi
+
+# Build for an embedded target, without std, if told to.
+if [ "${DO_NO_STD-false}" = true ]; then
+    rustup target add thumbv7em-none-eabihf
+    cargo build --target thumbv7em-none-eabihf --no-default-features
+    cargo build --target thumbv7em-none-eabihf --no-default-features --features="zeroize rand_core"
+fi
+
+# Build the C bindings as a static library and run the C test program
+# against them, if told to.
+if [ "${DO_FFI-false}" = true ]; then
+    cargo rustc --lib --features ffi --crate-type staticlib
+    cc -std=c99 -Wall -Wextra -Werror -Iinclude contrib/ffi-test.c \
+        target/debug/libcodex32.a -lpthread -ldl -lm -o target/ffi-test
+    target/ffi-test
+    # Check that the header is up to date
+    if command -v cbindgen > /dev/null; then
+        cbindgen --config cbindgen.toml --output target/codex32.h
+        diff -u include/codex32.h target/codex32.h
+    fi
+fi
+
+# Build the WebAssembly bindings and generate their JavaScript glue and
+# TypeScript declarations, if told to. Requires `wasm-bindgen-cli` of the
+# same version as the `wasm-bindgen` dependency.
+if [ "${DO_WASM-false}" = true ]; then
+    rustup target add wasm32-unknown-unknown
+    cargo rustc --lib --release --target wasm32-unknown-unknown \
+        --features "wasm-bindgen zeroize" --crate-type cdylib
+    wasm-bindgen --target web --out-dir target/pkg \
+        target/wasm32-unknown-unknown/release/codex32.wasm
+    test -f target/pkg/codex32.d.ts
+fi
+
+# Run each fuzz target for a while, starting from the corpus of BIP-93
+# vectors, if told to. Requires `cargo-fuzz` and a nightly toolchain.
+if [ "${DO_FUZZ-false}" = true ]; then
+    (
+        cd fuzz
+        for target in $(cargo fuzz list); do
+            cargo +nightly fuzz run "$target" -- -max_total_time="${FUZZ_TIME-60}"
+        done
+    )
+fi
+
+# Build the Python bindings into a virtualenv and run their tests against the
+# BIP-93 vectors, if told to.
+if [ "${DO_PYTHON-false}" = true ]; then
+    (
+        cd codex32-py
+        python3 -m venv ../target/venv
+        . ../target/venv/bin/activate
+        pip install maturin pytest
+        maturin develop
+        pytest
+    )
+fi
+
+# Compare another implementation against this one, if told to. The command
+# in CONFORMANCE_CMD must speak the protocol described in
+# src/bin/conformance.rs; by default this runs the Python bindings, and so
+# must come after DO_PYTHON.
+if [ "${DO_CONFORMANCE-false}" = true ]; then
+    cargo run --bin conformance -- --iterations "${CONFORMANCE_ITERATIONS-100}" \
+        -- ${CONFORMANCE_CMD-target/venv/bin/python3 contrib/conformance.py}
 fi
 
 # Build the docs if told to (this only works with the nightly toolchain)
@@ -0,0 +1,44 @@
+// Rust Codex32 Library and Reference Implementation
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Encodes arbitrary seeds, with the threshold, share index and HRP case
+//! taken from the first two bytes of input, checking that the data survives
+//! a round trip through the string and its fixed-capacity equivalent.
+
+#![no_main]
+
+use codex32::{Codex32Buf, Codex32String, Fe};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 2 {
+        return;
+    }
+    let threshold = usize::from(data[0] % 11);
+    let share_index = Fe::from_u8(data[1] & 0x1f).unwrap();
+    let hrp = if data[1] & 0x20 == 0 { "ms" } else { "MS2" };
+    let seed = &data[2..];
+
+    let c32 = match Codex32String::from_seed(hrp, threshold, "fuzz", share_index, seed) {
+        Ok(c32) => c32,
+        Err(_) => {
+            assert!(Codex32Buf::from_seed(hrp, threshold, "fuzz", share_index, seed).is_err());
+            return;
+        }
+    };
+    assert_eq!(&c32.parts().data()[..], seed);
+
+    let parsed = Codex32String::from_string(c32.expose_secret().into()).unwrap();
+    assert_eq!(&parsed.parts().data()[..], seed);
+    let buf = Codex32Buf::from_seed(hrp, threshold, "fuzz", share_index, seed).unwrap();
+    assert_eq!(buf.expose_secret(), c32.expose_secret());
+});
@@ -0,0 +1,61 @@
+// Rust Codex32 Library and Reference Implementation
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Interpolates arbitrary sets of strings, given one per line after a
+//! first line holding the target index, checking that any result is itself
+//! a valid share compatible with the inputs.
+
+#![no_main]
+
+use codex32::{Codex32String, Fe};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let s = match std::str::from_utf8(data) {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    let mut lines = s.lines();
+    let target = match lines.next().map(str::parse::<Fe>) {
+        Some(Ok(fe)) => fe,
+        _ => return,
+    };
+    let shares: Vec<Codex32String> = lines
+        .filter_map(|line| Codex32String::from_string(line.into()).ok())
+        .collect();
+
+    let result = match Codex32String::interpolate_at(&shares, target) {
+        Ok(result) => result,
+        Err(_) => return,
+    };
+    let parts = result.parts();
+    let parts0 = shares[0].parts();
+    // A single share is a constant polynomial, so is its own interpolation
+    if shares.len() > 1 {
+        assert_eq!(parts.share_index(), target);
+    }
+    assert_eq!(parts.threshold(), parts0.threshold());
+    assert_eq!(parts.id(), parts0.id());
+    assert_eq!(
+        result.expose_secret().len(),
+        shares[0].expose_secret().len()
+    );
+    Codex32String::from_string(result.expose_secret().into()).unwrap();
+
+    // Interpolating at an input's index gives that input back
+    let index0 = parts0.share_index();
+    assert_eq!(
+        Codex32String::interpolate_at(&shares, index0).unwrap(),
+        shares[0]
+    );
+});
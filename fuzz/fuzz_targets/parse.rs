@@ -0,0 +1,52 @@
+// Rust Codex32 Library and Reference Implementation
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Parses arbitrary strings, checking that valid strings can be broken into
+//! parts, re-encoded from their data, and agree with the fixed-capacity
+//! parser, and that error correction never panics.
+
+#![no_main]
+
+use codex32::{Codex32Buf, Codex32String};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let s = match std::str::from_utf8(data) {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    let buf = s.parse::<Codex32Buf>();
+    let _ = Codex32String::from_string_corrected(s.into());
+    let c32 = match Codex32String::from_string(s.into()) {
+        Ok(c32) => c32,
+        Err(_) => {
+            assert!(buf.is_err());
+            return;
+        }
+    };
+    assert_eq!(buf.unwrap().expose_secret(), c32.expose_secret());
+    let _ = format!("{:?}", c32);
+
+    // data -> string -> data
+    let parts = c32.parts();
+    let seed = parts.data();
+    if let Ok(reencoded) = Codex32String::from_seed(
+        &parts.hrp().to_lowercase(),
+        parts.threshold(),
+        &parts.id().to_lowercase(),
+        parts.share_index(),
+        &seed,
+    ) {
+        assert_eq!(&reencoded.parts().data()[..], &seed[..]);
+    }
+});
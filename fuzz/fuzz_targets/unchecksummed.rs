@@ -0,0 +1,40 @@
+// Rust Codex32 Library and Reference Implementation
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Checksums arbitrary strings, checking that the results are accepted by
+//! the parser whenever they have a valid length.
+
+#![no_main]
+
+use codex32::Codex32String;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let s = match std::str::from_utf8(data) {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    let c32 = match Codex32String::from_unchecksummed_string(s.into()) {
+        Ok(c32) => c32,
+        Err(_) => return,
+    };
+    let secret = c32.expose_secret();
+    assert!(secret.starts_with(s));
+    let _ = c32.parts().data();
+
+    let len = secret.len();
+    if (48..94).contains(&len) || (125..128).contains(&len) {
+        let parsed = Codex32String::from_string(secret.into()).unwrap();
+        assert_eq!(parsed, c32);
+    }
+});
@@ -0,0 +1,259 @@
+/*
+ * Rust Codex32 Library and Reference Implementation
+ *
+ * To the extent possible under law, the author(s) have dedicated all
+ * copyright and related and neighboring rights to this software to
+ * the public domain worldwide. This software is distributed without
+ * any warranty.
+ *
+ * You should have received a copy of the CC0 Public Domain Dedication
+ * along with this software.
+ * If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+ */
+
+#ifndef CODEX32_H
+#define CODEX32_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result of a call into the library, corresponding to the variants of the
+// Rust library's `Error`, or to problems with the arguments themselves
+//
+// The numeric values are part of the ABI and will not change.
+typedef enum {
+  // Success
+  CODEX32_ERROR_OK = 0,
+  // A required pointer argument was null
+  CODEX32_ERROR_NULL_POINTER = 1,
+  // A string argument was not valid UTF-8
+  CODEX32_ERROR_INVALID_UTF8 = 2,
+  // A character was not a valid bech32 character
+  CODEX32_ERROR_FIELD = 3,
+  // An identifier was not four characters long
+  CODEX32_ERROR_ID_NOT_LENGTH4 = 4,
+  // A payload ended in an entirely extraneous character
+  CODEX32_ERROR_INCOMPLETE_GROUP = 5,
+  // A codex32 string had an illegal length
+  CODEX32_ERROR_INVALID_LENGTH = 6,
+  // A character was not in the bech32 alphabet, or was not ASCII
+  CODEX32_ERROR_INVALID_CHAR = 7,
+  // A string mixed upper and lower case
+  CODEX32_ERROR_INVALID_CASE = 8,
+  // A string had an invalid checksum
+  CODEX32_ERROR_INVALID_CHECKSUM = 9,
+  // A threshold character was not 0 or 2 through 9
+  CODEX32_ERROR_INVALID_THRESHOLD = 10,
+  // A threshold was not 0 or 2 through 9
+  CODEX32_ERROR_INVALID_THRESHOLD_N = 11,
+  // A share index was not allowed (only `s` is, for a threshold of 0)
+  CODEX32_ERROR_INVALID_SHARE_INDEX = 12,
+  // A set of shares did not all have the same length
+  CODEX32_ERROR_MISMATCHED_LENGTH = 13,
+  // A set of shares did not all have the same HRP
+  CODEX32_ERROR_MISMATCHED_HRP = 14,
+  // A set of shares did not all have the same threshold
+  CODEX32_ERROR_MISMATCHED_THRESHOLD = 15,
+  // A set of shares did not all have the same identifier
+  CODEX32_ERROR_MISMATCHED_ID = 16,
+  // A set of re-sharing contributions did not all have the same share index
+  CODEX32_ERROR_MISMATCHED_SHARE_INDEX = 17,
+  // Shares were refreshed without changing their identifier
+  CODEX32_ERROR_UNCHANGED_ID = 18,
+  // A share index was repeated in a set of shares
+  CODEX32_ERROR_REPEATED_INDEX = 19,
+  // A share's index was not among those declared up front
+  CODEX32_ERROR_UNEXPECTED_INDEX = 20,
+  // A set of shares had fewer shares than its threshold
+  CODEX32_ERROR_THRESHOLD_NOT_PASSED = 21,
+  // More shares were requested than there are share indices
+  CODEX32_ERROR_TOO_MANY_SHARES = 22,
+  // A seed was not 16 to 64 bytes long
+  CODEX32_ERROR_INVALID_SEED_LENGTH = 23,
+  // A die roll was not a valid observation of that die
+  CODEX32_ERROR_INVALID_DIE_ROLL = 24,
+  // Not enough entropy was given for a seed of the requested length
+  CODEX32_ERROR_NOT_ENOUGH_ENTROPY = 25,
+  // An output buffer was too small
+  CODEX32_ERROR_BUFFER_TOO_SMALL = 26,
+  // The BIP-32 master key derived from a seed was invalid
+  CODEX32_ERROR_INVALID_MASTER_KEY = 27,
+  // A string could not be corrected by substituting a single character
+  CODEX32_ERROR_UNCORRECTABLE = 28,
+  // A string had no separator `1` between its HRP and data part
+  CODEX32_ERROR_MISSING_SEPARATOR = 29,
+} codex32_error;
+
+// An opaque handle to a valid codex32 string
+typedef struct codex32_string codex32_string;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Checks that a NUL-terminated string is a valid codex32 string, without
+// copying it.
+//
+// # Safety
+//
+// `s` must be null or point to a NUL-terminated string.
+codex32_error codex32_validate(const char *s);
+
+// Parses a NUL-terminated string as a codex32 string, writing a new handle
+// to `out`.
+//
+// # Safety
+//
+// `s` must be null or point to a NUL-terminated string, and `out` must be
+// null or valid for writes.
+codex32_error codex32_parse(const char *s, codex32_string **out);
+
+// Parses a NUL-terminated string as a codex32 string, correcting a single
+// substituted character if necessary, and writes a new handle to `out`.
+//
+// If a character was corrected, its position is written to
+// `corrected_pos`; otherwise `SIZE_MAX` is. See
+// `Codex32String::from_str_corrected` in the Rust library.
+//
+// # Safety
+//
+// `s` must be null or point to a NUL-terminated string, and `out` and
+// `corrected_pos` must each be null or valid for writes.
+codex32_error codex32_correct(const char *s, codex32_string **out, size_t *corrected_pos);
+
+// Creates a codex32 string encoding `seed`, with the given HRP, threshold,
+// identifier and share index, and writes a new handle to `out`.
+//
+// `share_idx` is a single bech32 character, which must be `s` for a
+// threshold of 0.
+//
+// # Safety
+//
+// `hrp` and `id` must each be null or point to a NUL-terminated string,
+// `seed` must be valid for reads of `seed_len` bytes, and `out` must be
+// null or valid for writes.
+codex32_error codex32_from_seed(const char *hrp,
+                                size_t threshold,
+                                const char *id,
+                                char share_idx,
+                                const uint8_t *seed,
+                                size_t seed_len,
+                                codex32_string **out);
+
+// Splits `seed` into `n_shares` shares, deterministically, and writes a
+// new handle for each to the array `shares_out`.
+//
+// See `Codex32String::split_deterministic` in the Rust library;
+// `extra_entropy` may be null if `extra_entropy_len` is 0.
+//
+// # Safety
+//
+// `hrp` and `id` must each be null or point to a NUL-terminated string,
+// `seed` and `extra_entropy` must be valid for reads of `seed_len` and
+// `extra_entropy_len` bytes respectively, and `shares_out` must be null or
+// valid for writes of `n_shares` handles.
+codex32_error codex32_split(const char *hrp,
+                            size_t threshold,
+                            const char *id,
+                            const uint8_t *seed,
+                            size_t seed_len,
+                            const uint8_t *extra_entropy,
+                            size_t extra_entropy_len,
+                            size_t n_shares,
+                            codex32_string **shares_out);
+
+// Interpolates an array of share handles at the index `target`, a single
+// bech32 character, and writes a new handle to `out`.
+//
+// Using the target `s` recovers the master seed as a codex32 string.
+//
+// # Safety
+//
+// `shares` must be null or valid for reads of `n_shares` handles, each of
+// which must be null or have been returned by this library and not freed,
+// and `out` must be null or valid for writes.
+codex32_error codex32_interpolate(const codex32_string *const *shares,
+                                  size_t n_shares,
+                                  char target,
+                                  codex32_string **out);
+
+// Recovers the master seed from an array of share handles, writing it to
+// `seed_out` and its length to `seed_len`.
+//
+// Errors with `CODEX32_ERROR_BUFFER_TOO_SMALL` if `seed_out_len` is less
+// than the length of the seed, which is at most 64 bytes.
+//
+// # Safety
+//
+// `shares` must be null or valid for reads of `n_shares` handles, each of
+// which must be null or have been returned by this library and not freed,
+// `seed_out` must be null or valid for writes of `seed_out_len` bytes, and
+// `seed_len` must be null or valid for writes.
+codex32_error codex32_recover_seed(const codex32_string *const *shares,
+                                   size_t n_shares,
+                                   uint8_t *seed_out,
+                                   size_t seed_out_len,
+                                   size_t *seed_len);
+
+// The length of the string behind a handle, not including a NUL terminator,
+// or 0 if `s` is null
+//
+// # Safety
+//
+// `s` must be null or have been returned by this library and not freed.
+size_t codex32_string_len(const codex32_string *s);
+
+// Copies the string behind a handle into `buf`, followed by a NUL
+// terminator.
+//
+// Errors with `CODEX32_ERROR_BUFFER_TOO_SMALL` if `buf_len` is not at
+// least one more than `codex32_string_len`.
+//
+// # Safety
+//
+// `s` must be null or have been returned by this library and not freed,
+// and `buf` must be null or valid for writes of `buf_len` bytes.
+codex32_error codex32_string_copy(const codex32_string *s, char *buf, size_t buf_len);
+
+// Writes the payload of the string behind a handle, as bytes, to `out`,
+// and its length to `len`.
+//
+// For an `S` share, this is the master seed.
+//
+// # Safety
+//
+// `s` must be null or have been returned by this library and not freed,
+// `out` must be null or valid for writes of `out_len` bytes, and `len`
+// must be null or valid for writes.
+codex32_error codex32_string_data(const codex32_string *s,
+                                  uint8_t *out,
+                                  size_t out_len,
+                                  size_t *len);
+
+// Frees a handle, wiping the string from memory. Does nothing if `s` is
+// null.
+//
+// # Safety
+//
+// `s` must be null or have been returned by this library and not already
+// freed.
+void codex32_string_free(codex32_string *s);
+
+// Wipes a buffer, e.g. one filled by `codex32_string_copy`, in a way
+// which will not be optimized away. Does nothing if `buf` is null.
+//
+// # Safety
+//
+// `buf` must be null or valid for writes of `len` bytes.
+void codex32_wipe(uint8_t *buf, size_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CODEX32_H */
@@ -0,0 +1,574 @@
+// Rust Codex32 Library and Reference Implementation
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Conformance Harness
+//!
+//! This is a utility which compares another codex32 implementation against
+//! this one. It generates random shares, along with corrupted versions of
+//! them, sends them to the other implementation, and reports every request
+//! where the answer differs from the one this library gives.
+//!
+//! ```text
+//! conformance [--seed N] [--iterations N] -- <command> [args...]
+//! ```
+//!
+//! The other implementation is run as `<command>`, and is sent one request
+//! per line on its standard input. It must answer each request with a single
+//! line on its standard output, before the next request is sent:
+//!
+//! ```text
+//! validate <string>                    -> ok | error [message]
+//! interpolate <target> <share>...      -> ok <string> | error [message]
+//! correct <string>                     -> ok <string> | error [message]
+//! ```
+//!
+//! where `<target>` is a single bech32 character. `correct` should fix a
+//! single substituted character, and return the string unchanged if it is
+//! already valid. Any message after `error` is reported but not compared.
+//! A command which is not implemented may be answered with `unsupported`,
+//! in which case it is skipped.
+//!
+//! The same `--seed` always gives the same requests, so any divergence can
+//! be reproduced.
+//!
+
+use codex32::{Codex32String, Fe};
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{self, Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fmt};
+
+use bitcoin_hashes::{sha256, Hash};
+
+/// Deterministic random number generator, SHA256 of the seed and a counter
+struct Rng {
+    seed: u64,
+    counter: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        Rng { seed, counter: 0 }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut input = [0; 16];
+        input[..8].copy_from_slice(&self.seed.to_le_bytes());
+        input[8..].copy_from_slice(&self.counter.to_le_bytes());
+        self.counter += 1;
+        let hash = sha256::Hash::hash(&input);
+        let mut out = [0; 8];
+        out.copy_from_slice(&hash[..8]);
+        u64::from_le_bytes(out)
+    }
+
+    /// A number in `0..n`
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// A number in `lo..=hi`
+    fn range(&mut self, lo: usize, hi: usize) -> usize {
+        lo + self.below(hi - lo + 1)
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next_u64() as u8).collect()
+    }
+
+    fn fe(&mut self) -> Fe {
+        Fe::from_u8(self.below(32) as u8).unwrap()
+    }
+
+    fn shuffle<T>(&mut self, v: &mut [T]) {
+        for i in (1..v.len()).rev() {
+            let j = self.below(i + 1);
+            v.swap(i, j);
+        }
+    }
+}
+
+/// An answer to a request
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Response {
+    /// Success, with the resulting string if there is one
+    Ok(Option<String>),
+    /// Failure, with an optional message which is not compared
+    Error(String),
+    /// The command is not implemented
+    Unsupported,
+}
+
+impl Response {
+    fn parse(line: &str) -> Option<Response> {
+        let mut words = line
+            .trim_end_matches(|c| c == '\r' || c == '\n')
+            .splitn(2, ' ');
+        match (words.next(), words.next()) {
+            (Some("ok"), None) => Some(Response::Ok(None)),
+            (Some("ok"), Some(s)) => Some(Response::Ok(Some(s.into()))),
+            (Some("error"), msg) => Some(Response::Error(msg.unwrap_or("").into())),
+            (Some("unsupported"), None) => Some(Response::Unsupported),
+            _ => None,
+        }
+    }
+
+    /// Whether two answers agree, ignoring any error messages
+    fn agrees_with(&self, other: &Response) -> bool {
+        match (self, other) {
+            (Response::Ok(a), Response::Ok(b)) => a == b,
+            (Response::Error(_), Response::Error(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<E: fmt::Debug> From<Result<Option<String>, E>> for Response {
+    fn from(res: Result<Option<String>, E>) -> Response {
+        match res {
+            Ok(s) => Response::Ok(s),
+            Err(e) => Response::Error(format!("{:?}", e)),
+        }
+    }
+}
+
+impl fmt::Display for Response {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Response::Ok(None) => f.write_str("ok"),
+            Response::Ok(Some(s)) => write!(f, "ok {}", s),
+            Response::Error(msg) if msg.is_empty() => f.write_str("error"),
+            Response::Error(msg) => write!(f, "error {}", msg),
+            Response::Unsupported => f.write_str("unsupported"),
+        }
+    }
+}
+
+/// The answers this library gives to each request
+fn expected(request: &str) -> Response {
+    let mut words = request.split(' ');
+    match words.next() {
+        Some("validate") => {
+            let s = words.next().unwrap_or("");
+            Codex32String::from_string(s.into()).map(|_| None).into()
+        }
+        Some("interpolate") => {
+            let target = words.next().unwrap_or("").parse::<Fe>();
+            let shares = words
+                .map(|s| Codex32String::from_string(s.into()))
+                .collect::<Result<Vec<_>, _>>();
+            match (target, shares) {
+                (Ok(target), Ok(shares)) => Codex32String::interpolate_at(&shares, target)
+                    .map(|c32| Some(c32.expose_secret().into()))
+                    .into(),
+                (Err(e), _) | (_, Err(e)) => Response::Error(format!("{:?}", e)),
+            }
+        }
+        Some("correct") => {
+            let s = words.next().unwrap_or("");
+            Codex32String::from_string_corrected(s.into())
+                .map(|(c32, _)| Some(c32.expose_secret().into()))
+                .into()
+        }
+        _ => Response::Unsupported,
+    }
+}
+
+/// Substitutes `n` distinct characters of the data part of a valid string
+fn substitute(rng: &mut Rng, s: &str, n: usize) -> String {
+    let mut chars: Vec<char> = s.chars().collect();
+    // Never touch the HRP `ms` or the separator
+    let mut positions: Vec<usize> = (3..chars.len()).collect();
+    rng.shuffle(&mut positions);
+    for &pos in &positions[..n] {
+        let upper = chars[pos].is_ascii_uppercase();
+        // unwraps ok since the string is valid and the offset is nonzero
+        let fe = Fe::from_char(chars[pos]).unwrap() + Fe::from_u8(rng.range(1, 31) as u8).unwrap();
+        chars[pos] = if upper {
+            fe.to_char().to_ascii_uppercase()
+        } else {
+            fe.to_char()
+        };
+    }
+    chars.into_iter().collect()
+}
+
+/// Applies a random corruption to a valid string
+fn corrupt(rng: &mut Rng, s: &str) -> String {
+    let mut chars: Vec<char> = s.chars().collect();
+    // Never touch the HRP `ms` or the separator
+    let data_start = 3;
+    match rng.below(6) {
+        // Substitutions, which should always be detected
+        0 => {
+            let n = rng.range(1, 4);
+            return substitute(rng, s, n);
+        }
+        // Mixed case
+        1 => {
+            let letters: Vec<usize> = (0..chars.len())
+                .filter(|&i| chars[i].is_ascii_alphabetic())
+                .collect();
+            let pos = letters[rng.below(letters.len())];
+            chars[pos] = if chars[pos].is_ascii_uppercase() {
+                chars[pos].to_ascii_lowercase()
+            } else {
+                chars[pos].to_ascii_uppercase()
+            };
+        }
+        // Truncation
+        2 => {
+            let len = rng.range(0, chars.len() - 1);
+            chars.truncate(len);
+        }
+        // A character outside of the bech32 alphabet
+        3 => {
+            let pos = rng.range(data_start, chars.len() - 1);
+            chars[pos] = ['b', 'i', 'o', '1', 'B', 'I', 'O', '!'][rng.below(8)];
+        }
+        // A dropped separator
+        4 => {
+            chars.remove(data_start - 1);
+        }
+        // An inserted character
+        _ => {
+            let pos = rng.range(data_start, chars.len());
+            let ch = rng.fe().to_char();
+            chars.insert(pos, ch);
+        }
+    }
+    chars.into_iter().collect()
+}
+
+/// Generates the requests for a single random split
+fn requests(rng: &mut Rng) -> Vec<String> {
+    let seed_len = match rng.below(4) {
+        0 => rng.range(63, 64),
+        _ => rng.range(16, 44),
+    };
+    let seed = rng.bytes(seed_len);
+    let threshold = rng.range(2, 9);
+    let id: String = (0..4).map(|_| rng.fe().to_char()).collect();
+    let n_shares = rng.range(threshold, (threshold + 3).min(31));
+    let entropy = rng.bytes(32);
+    let mut shares: Vec<String> =
+        Codex32String::split_deterministic("ms", threshold, &id, &seed, &entropy, n_shares)
+            .unwrap()
+            .iter()
+            .map(|c32| c32.expose_secret().to_owned())
+            .collect();
+    shares.push(
+        Codex32String::from_seed("ms", threshold, &id, Fe::S, &seed)
+            .unwrap()
+            .expose_secret()
+            .to_owned(),
+    );
+    if rng.below(4) == 0 {
+        for share in &mut shares {
+            *share = share.to_ascii_uppercase();
+        }
+    }
+    rng.shuffle(&mut shares);
+
+    let mut ret = vec![];
+    // Validation
+    for share in &shares {
+        ret.push(format!("validate {}", share));
+        ret.push(format!("validate {}", corrupt(rng, share)));
+    }
+
+    // Interpolation, with enough shares and with one too few
+    let target = rng.fe().to_char();
+    ret.push(format!(
+        "interpolate {} {}",
+        target,
+        shares[..threshold].join(" ")
+    ));
+    ret.push(format!(
+        "interpolate {} {}",
+        target,
+        shares[..threshold - 1].join(" ")
+    ));
+
+    // Correction, of zero, one and two substitutions
+    let share = &shares[rng.below(shares.len())];
+    for n in 0..3 {
+        ret.push(format!("correct {}", substitute(rng, share, n)));
+    }
+    ret
+}
+
+/// Another implementation, answering requests one line at a time
+trait Implementation {
+    fn query(&mut self, request: &str) -> io::Result<String>;
+}
+
+/// An implementation run as a subprocess
+struct Subprocess {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Subprocess {
+    fn spawn(command: &[String]) -> io::Result<Subprocess> {
+        let mut child = Command::new(&command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        // unwraps ok since both were piped above
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Ok(Subprocess {
+            child,
+            stdin,
+            stdout,
+        })
+    }
+}
+
+impl Implementation for Subprocess {
+    fn query(&mut self, request: &str) -> io::Result<String> {
+        writeln!(self.stdin, "{}", request)?;
+        self.stdin.flush()?;
+        let mut line = String::new();
+        if self.stdout.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "implementation exited",
+            ));
+        }
+        Ok(line)
+    }
+}
+
+impl Drop for Subprocess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// A request to which the other implementation gave a different answer
+struct Divergence {
+    request: String,
+    expected: Response,
+    got: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "request:  {}", self.request)?;
+        writeln!(f, "expected: {}", self.expected)?;
+        write!(f, "got:      {}", self.got)
+    }
+}
+
+/// The outcome of a run
+#[derive(Default)]
+struct Report {
+    requests: usize,
+    unsupported: usize,
+    divergences: Vec<Divergence>,
+}
+
+/// Sends `iterations` splits' worth of requests to an implementation
+fn run<I: Implementation>(imp: &mut I, seed: u64, iterations: usize) -> io::Result<Report> {
+    let mut rng = Rng::new(seed);
+    let mut report = Report::default();
+    for _ in 0..iterations {
+        for request in requests(&mut rng) {
+            let line = imp.query(&request)?;
+            let line = line.trim_end_matches(|c| c == '\r' || c == '\n');
+            let expected = expected(&request);
+            report.requests += 1;
+            match Response::parse(line) {
+                Some(Response::Unsupported) => report.unsupported += 1,
+                Some(ref got) if got.agrees_with(&expected) => {}
+                _ => report.divergences.push(Divergence {
+                    request,
+                    expected,
+                    got: line.into(),
+                }),
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: conformance [--seed N] [--iterations N] -- <command> [args...]");
+    process::exit(2);
+}
+
+fn main() {
+    let mut seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut iterations = 100;
+    let mut args = env::args().skip(1);
+    let mut command = vec![];
+    while let Some(arg) = args.next() {
+        let mut number = || {
+            args.next()
+                .and_then(|n| n.parse().ok())
+                .unwrap_or_else(|| usage())
+        };
+        match arg.as_str() {
+            "--seed" => seed = number(),
+            "--iterations" => iterations = number() as usize,
+            "--" => {
+                command.extend(args.by_ref());
+                break;
+            }
+            _ => usage(),
+        }
+    }
+    if command.is_empty() {
+        usage();
+    }
+
+    println!("Seed: {}", seed);
+    let result = Subprocess::spawn(&command).and_then(|mut imp| run(&mut imp, seed, iterations));
+    let report = match result {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Failed to run {}: {}", command[0], e);
+            process::exit(2);
+        }
+    };
+    for divergence in &report.divergences {
+        println!("{}\n", divergence);
+    }
+    println!(
+        "Total: {} requests, {} unsupported, {} divergences",
+        report.requests,
+        report.unsupported,
+        report.divergences.len()
+    );
+    if !report.divergences.is_empty() {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An implementation which answers every request with a function
+    struct InProcess<F>(F);
+
+    impl<F: FnMut(&str) -> String> Implementation for InProcess<F> {
+        fn query(&mut self, request: &str) -> io::Result<String> {
+            Ok((self.0)(request) + "\n")
+        }
+    }
+
+    #[test]
+    fn agrees_with_self() {
+        let mut imp = InProcess(|req: &str| expected(req).to_string());
+        let report = run(&mut imp, 0, 5).unwrap();
+        assert!(report.requests > 0);
+        assert_eq!(report.unsupported, 0);
+        assert!(report.divergences.is_empty());
+    }
+
+    #[test]
+    fn requests_are_reproducible() {
+        let (mut rng_a, mut rng_b) = (Rng::new(7), Rng::new(7));
+        for _ in 0..5 {
+            assert_eq!(requests(&mut rng_a), requests(&mut rng_b));
+        }
+        assert_ne!(requests(&mut Rng::new(7)), requests(&mut Rng::new(8)));
+    }
+
+    #[test]
+    fn requests_exercise_both_outcomes() {
+        let mut rng = Rng::new(0);
+        let mut ok = 0;
+        let mut err = 0;
+        for request in (0..5).flat_map(|_| requests(&mut rng)) {
+            match expected(&request) {
+                Response::Ok(_) => ok += 1,
+                Response::Error(_) => err += 1,
+                Response::Unsupported => panic!("unknown request {}", request),
+            }
+        }
+        assert!(ok > 0 && err > 0);
+    }
+
+    #[test]
+    fn unsupported_commands() {
+        let mut imp = InProcess(|req: &str| {
+            if req.starts_with("validate") {
+                expected(req).to_string()
+            } else {
+                "unsupported".into()
+            }
+        });
+        let report = run(&mut imp, 0, 5).unwrap();
+        assert!(report.unsupported > 0);
+        assert!(report.divergences.is_empty());
+    }
+
+    #[test]
+    fn detects_divergences() {
+        // Accepts everything
+        let mut imp = InProcess(|req: &str| match expected(req) {
+            Response::Error(_) => "ok".into(),
+            resp => resp.to_string(),
+        });
+        let report = run(&mut imp, 0, 5).unwrap();
+        assert!(!report.divergences.is_empty());
+
+        // Corrects nothing
+        let mut imp = InProcess(|req: &str| {
+            if req.starts_with("correct") {
+                format!("ok {}", req.split(' ').nth(1).unwrap())
+            } else {
+                expected(req).to_string()
+            }
+        });
+        let report = run(&mut imp, 0, 5).unwrap();
+        assert!(!report.divergences.is_empty());
+        assert!(report
+            .divergences
+            .iter()
+            .all(|d| d.request.starts_with("correct")));
+
+        // Speaks the wrong protocol
+        let mut imp = InProcess(|_: &str| "true".to_string());
+        let report = run(&mut imp, 0, 1).unwrap();
+        assert_eq!(report.divergences.len(), report.requests);
+    }
+
+    #[test]
+    fn response_parsing() {
+        assert_eq!(Response::parse("ok\n"), Some(Response::Ok(None)));
+        assert_eq!(
+            Response::parse("ok ms10test"),
+            Some(Response::Ok(Some("ms10test".into())))
+        );
+        assert_eq!(Response::parse("error"), Some(Response::Error("".into())));
+        assert_eq!(
+            Response::parse("error bad checksum"),
+            Some(Response::Error("bad checksum".into()))
+        );
+        assert_eq!(Response::parse("unsupported"), Some(Response::Unsupported));
+        assert_eq!(Response::parse("OK"), None);
+        assert_eq!(Response::parse(""), None);
+        assert!(Response::Error("a".into()).agrees_with(&Response::Error("b".into())));
+        assert!(!Response::Ok(None).agrees_with(&Response::Error("".into())));
+    }
+}
@@ -0,0 +1,40 @@
+// Rust Codex32 Library and Reference Implementation
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Volvelle Artwork
+//!
+//! This is a simple utility that writes out SVG artwork for each of the
+//! codex32 volvelles, as `<name>.svg`, into the directory given on the
+//! command line (or the current directory, if none is given).
+//!
+
+use codex32::volvelle::{Kind, Volvelle};
+use std::{env, fs, path::PathBuf};
+
+fn main() {
+    let dir = env::args_os()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    for kind in &Kind::all() {
+        let path = dir.join(format!("{}.svg", kind.name()));
+        let svg = Volvelle::new(*kind).to_svg();
+        if let Err(e) = fs::write(&path, svg) {
+            eprintln!("Failed to write {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+        println!("Wrote {}", path.display());
+    }
+}
@@ -0,0 +1,427 @@
+// Rust Codex32 Library and Reference Implementation
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Fixed-capacity Strings
+//!
+//! A codex32 string stored inline in a fixed-size array, with parsing,
+//! checksumming and interpolation which never touch the heap.
+//!
+//! The only allocations are those needed to construct an error which
+//! carries a string, such as [`Error::InvalidChecksum`] or
+//! [`Error::MismatchedHrp`].
+//!
+
+use core::convert::TryFrom;
+use core::{fmt, hash, str};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+use super::{
+    check_compatible, check_indices, check_string, is_upper, lagrange_coefficient, to_char,
+    Codex32String, Error, Id, Parts,
+};
+use crate::checksum;
+use crate::field::Fe;
+
+/// The maximum length of a codex32 string, in bytes
+pub const MAX_LEN: usize = 127;
+
+/// A codex32 string, containing a valid checksum, stored in a fixed-size
+/// buffer
+///
+/// Like [`Codex32String`], its `Debug` output shows only the header. To get
+/// at the full string, use [`Codex32Buf::expose_secret`].
+#[derive(Clone)]
+pub struct Codex32Buf {
+    buf: [u8; MAX_LEN],
+    len: usize,
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Codex32Buf {
+    fn drop(&mut self) {
+        self.buf[..].zeroize();
+    }
+}
+
+impl PartialEq for Codex32Buf {
+    fn eq(&self, other: &Codex32Buf) -> bool {
+        self.expose_secret() == other.expose_secret()
+    }
+}
+
+impl Eq for Codex32Buf {}
+
+impl hash::Hash for Codex32Buf {
+    fn hash<H: hash::Hasher>(&self, h: &mut H) {
+        self.expose_secret().hash(h)
+    }
+}
+
+impl fmt::Debug for Codex32Buf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts = self.parts();
+        f.debug_struct("Codex32Buf")
+            .field("hrp", &parts.hrp)
+            .field("threshold", &parts.threshold)
+            .field("id", &parts.id)
+            .field("share_index", &parts.share_index)
+            .field("payload", &"<redacted>")
+            .finish()
+    }
+}
+
+impl str::FromStr for Codex32Buf {
+    type Err = Error;
+
+    /// Parses an already-checksummed string
+    fn from_str(s: &str) -> Result<Codex32Buf, Error> {
+        check_string(s)?;
+        let mut ret = Codex32Buf::empty();
+        ret.push_str(s)?;
+        Ok(ret)
+    }
+}
+
+impl<'a> TryFrom<&'a Codex32String> for Codex32Buf {
+    type Error = Error;
+    fn try_from(s: &'a Codex32String) -> Result<Codex32Buf, Error> {
+        let mut ret = Codex32Buf::empty();
+        ret.push_str(s.expose_secret())?;
+        Ok(ret)
+    }
+}
+
+impl<'a> From<&'a Codex32Buf> for Codex32String {
+    fn from(s: &'a Codex32Buf) -> Codex32String {
+        Codex32String(s.expose_secret().into())
+    }
+}
+
+impl Codex32Buf {
+    /// An empty buffer, for internal use
+    fn empty() -> Codex32Buf {
+        Codex32Buf {
+            buf: [0; MAX_LEN],
+            len: 0,
+        }
+    }
+
+    /// Appends a single ASCII character
+    fn push(&mut self, ch: char) -> Result<(), Error> {
+        if self.len == MAX_LEN {
+            return Err(Error::InvalidLength(self.len + 1));
+        }
+        self.buf[self.len] = ch as u8; // cast ok since we only push ASCII
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Appends a string of ASCII characters
+    fn push_str(&mut self, s: &str) -> Result<(), Error> {
+        if self.len + s.len() > MAX_LEN {
+            return Err(Error::InvalidLength(self.len + s.len()));
+        }
+        if let Some(ch) = s.chars().find(|ch| !ch.is_ascii()) {
+            return Err(Error::InvalidChar(ch));
+        }
+        self.buf[self.len..self.len + s.len()].copy_from_slice(s.as_bytes());
+        self.len += s.len();
+        Ok(())
+    }
+
+    /// The full string, including the (possibly secret) payload
+    pub fn expose_secret(&self) -> &str {
+        // unwrap ok since we only ever store ASCII
+        str::from_utf8(&self.buf[..self.len]).unwrap()
+    }
+
+    /// Break the string up into its constituent parts
+    pub fn parts(&self) -> Parts<'_> {
+        // unwrap OK since we validated the input on construction
+        Parts::parse(self.expose_secret()).unwrap()
+    }
+
+    /// Creates a share from bare seed data
+    ///
+    /// As for [`Codex32String::from_seed`], the case of the output matches
+    /// that of the HRP, which must not be mixed-case.
+    pub fn from_seed(
+        hrp: &str,
+        threshold: usize,
+        id: &str,
+        share_idx: Fe,
+        data: &[u8],
+    ) -> Result<Codex32Buf, Error> {
+        let id: Id = id.parse()?;
+        let k = match threshold {
+            0 | 2..=9 => Fe::from_char(char::from(b'0' + threshold as u8)).unwrap(), // unwrap ok since a digit
+            x => return Err(Error::InvalidThresholdN(x)),
+        };
+        let upper = is_upper(hrp);
+        let case = |fe: Fe| to_char(fe, upper);
+
+        let mut ret = Codex32Buf::empty();
+        ret.push_str(hrp)?;
+        ret.push('1')?;
+        ret.push(case(k))?;
+        for ch in id.as_str().chars() {
+            ret.push(if upper { ch.to_ascii_uppercase() } else { ch })?;
+        }
+        ret.push(case(share_idx))?;
+
+        // Convert byte data to base 32
+        let mut next_u5 = 0;
+        let mut rem = 0;
+        for byte in data {
+            let u5 = (next_u5 << (5 - rem)) | byte >> (3 + rem);
+            ret.push(case(Fe::from_u8(u5).unwrap()))?;
+            next_u5 = byte & ((1 << (3 + rem)) - 1);
+            if rem >= 2 {
+                ret.push(case(Fe::from_u8(next_u5 >> (rem - 2)).unwrap()))?;
+                next_u5 &= (1 << (rem - 2)) - 1;
+            }
+            rem = (rem + 8) % 5;
+        }
+        if rem > 0 {
+            ret.push(case(Fe::from_u8(next_u5 << (5 - rem)).unwrap()))?;
+        }
+
+        // As in `Codex32String::from_seed`, choose the checksum by the length
+        // of the string rather than that of the seed
+        let mut checksum = if ret.len < 81 {
+            checksum::Engine::new_codex32_short()
+        } else {
+            checksum::Engine::new_codex32_long()
+        };
+        checksum.input_hrp(hrp)?;
+        checksum.input_data_str(&ret.expose_secret()[hrp.len() + 1..])?;
+        checksum.input_own_target();
+        for fe in checksum.residue() {
+            ret.push(case(*fe))?;
+        }
+
+        // Not every seed length gives a string of a length allowed by BIP-93
+        check_string(ret.expose_secret())?;
+        Ok(ret)
+    }
+
+    /// Interpolate a set of shares to derive a share at a specific index.
+    ///
+    /// Using the index `Fe::S` will recover the master seed.
+    pub fn interpolate_at(shares: &[Codex32Buf], target: Fe) -> Result<Codex32Buf, Error> {
+        if shares.is_empty() {
+            return Err(Error::ThresholdNotPassed {
+                threshold: 1,
+                n_shares: 0,
+            });
+        }
+        let s0_parts = shares[0].parts();
+        if s0_parts.threshold > shares.len() {
+            return Err(Error::ThresholdNotPassed {
+                threshold: s0_parts.threshold,
+                n_shares: shares.len(),
+            });
+        }
+
+        // There are only 32 possible indices, so any more shares than that
+        // must include a repeated index.
+        let mut indices = [Fe::Q; 32];
+        for (i, share) in shares.iter().enumerate() {
+            let parts = share.parts();
+            check_compatible(
+                shares[0].expose_secret(),
+                &s0_parts,
+                share.expose_secret(),
+                &parts,
+            )?;
+            if i < indices.len() {
+                indices[i] = parts.share_index;
+            } else {
+                return Err(Error::RepeatedIndex(parts.share_index));
+            }
+        }
+        let indices = &indices[..shares.len()];
+        if let Some(i) = check_indices(indices, target)? {
+            // If we're trying to output an input share, just output it directly.
+            return Ok(shares[i].clone());
+        }
+
+        let mut coeffs = [Fe::Q; 32];
+        for (i, coeff) in coeffs.iter_mut().enumerate().take(shares.len()) {
+            *coeff = lagrange_coefficient(indices, i, target);
+        }
+
+        let mut ret = Codex32Buf::empty();
+        ret.push_str(s0_parts.hrp)?;
+        ret.push('1')?;
+        let (start, end) = (ret.len, shares[0].len);
+
+        // Add up the translated shares, one share at a time
+        let mut payload = [Fe::Q; MAX_LEN];
+        for (share, coeff) in shares.iter().zip(coeffs.iter()) {
+            for (sum, byte) in payload[start..end].iter_mut().zip(&share.buf[start..end]) {
+                // unwrap ok since string is valid bech32
+                *sum += coeff.ct_mul(Fe::from_char(char::from(*byte)).unwrap());
+            }
+        }
+        let upper = is_upper(s0_parts.hrp);
+        for fe in &payload[start..end] {
+            ret.push(to_char(*fe, upper))?;
+        }
+        #[cfg(feature = "zeroize")]
+        for fe in payload.iter_mut() {
+            fe.zeroize();
+        }
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let s = "MS12NAMEA320ZYXWVUTSRQPNMLKJHGFEDCAXRPP870HKKQRM";
+        let buf: Codex32Buf = s.parse().unwrap();
+        assert_eq!(buf.expose_secret(), s);
+        assert_eq!(buf.parts().id, "NAME");
+        assert_eq!(buf.parts().share_index, Fe::A);
+        assert_eq!(
+            format!("{:?}", buf),
+            "Codex32Buf { hrp: \"MS\", threshold: 2, id: \"NAME\", share_index: Fe(29), payload: \"<redacted>\" }",
+        );
+
+        let string = Codex32String::from_string(s.into()).unwrap();
+        assert_eq!(Codex32Buf::try_from(&string).unwrap(), buf);
+        assert_eq!(Codex32String::from(&buf), string);
+
+        assert!(matches!(
+            "MS12NAMEA320ZYXWVUTSRQPNMLKJHGFEDCAXRPP870HKKQRN".parse::<Codex32Buf>(),
+            Err(Error::InvalidChecksum { .. }),
+        ));
+        assert!(matches!(
+            "ms12name".parse::<Codex32Buf>(),
+            Err(Error::InvalidLength(8)),
+        ));
+    }
+
+    #[test]
+    fn interpolate() {
+        // BIP-93 test vector 2
+        let share_ac: [Codex32Buf; 2] = [
+            "MS12NAMEA320ZYXWVUTSRQPNMLKJHGFEDCAXRPP870HKKQRM"
+                .parse()
+                .unwrap(),
+            "MS12NAMECACDEFGHJKLMNPQRSTUVWXYZ023FTR2GDZMPY6PN"
+                .parse()
+                .unwrap(),
+        ];
+        let share_d = Codex32Buf::interpolate_at(&share_ac, Fe::D).unwrap();
+        assert_eq!(
+            share_d.expose_secret(),
+            "MS12NAMEDLL4F8JLH4E5VDVULDLFXU2JHDNLSM97XVENRXEG"
+        );
+        let seed = Codex32Buf::interpolate_at(&share_ac, Fe::S).unwrap();
+        assert_eq!(
+            seed.expose_secret(),
+            "MS12NAMES6XQGUZTTXKEQNJSJZV4JV3NZ5K3KWGSPHUH6EVW"
+        );
+        let mut data = [0; 16];
+        assert_eq!(seed.parts().data_into(&mut data).unwrap(), 16);
+        assert_eq!(&data[..], &seed.parts().data()[..]);
+        assert!(matches!(
+            seed.parts().data_into(&mut [0; 15]),
+            Err(Error::BufferTooSmall {
+                needed: 16,
+                available: 15
+            }),
+        ));
+
+        assert!(matches!(
+            Codex32Buf::interpolate_at(&share_ac[..1], Fe::S),
+            Err(Error::ThresholdNotPassed { .. }),
+        ));
+        assert!(matches!(
+            Codex32Buf::interpolate_at(&[share_ac[0].clone(), share_ac[0].clone()], Fe::S),
+            Err(Error::RepeatedIndex(Fe::A)),
+        ));
+    }
+
+    #[test]
+    fn from_seed() {
+        // The seed from BIP-93 test vector 1, although that vector has
+        // nonzero padding bits, so its string is different
+        #[rustfmt::skip]
+        let seed = [
+            0x31, 0x8c, 0x63, 0x18, 0xc6, 0x31, 0x8c, 0x63,
+            0x18, 0xc6, 0x31, 0x8c, 0x63, 0x18, 0xc6, 0x31,
+        ];
+        let buf = Codex32Buf::from_seed("ms", 0, "test", Fe::S, &seed).unwrap();
+        let string = Codex32String::from_seed("ms", 0, "test", Fe::S, &seed).unwrap();
+        assert_eq!(buf.expose_secret(), string.expose_secret());
+        assert_eq!(&buf.parts().data()[..], &seed[..]);
+        let upper = Codex32Buf::from_seed("MS", 0, "TEST", Fe::S, &seed).unwrap();
+        assert_eq!(
+            upper.expose_secret(),
+            string.expose_secret().to_ascii_uppercase()
+        );
+
+        let data = [0xff; 64];
+        let buf = Codex32Buf::from_seed("ms", 0, "leet", Fe::S, &data).unwrap();
+        let string = Codex32String::from_seed("ms", 0, "leet", Fe::S, &data).unwrap();
+        assert_eq!(buf.expose_secret(), string.expose_secret());
+        assert_eq!(buf.expose_secret().len(), 127);
+
+        // With a two-character HRP, only seeds of 16 to 44 bytes and of 63 or
+        // 64 bytes give strings of a length allowed by BIP-93
+        for len in 0..=64 {
+            let buf = Codex32Buf::from_seed("ms", 0, "leet", Fe::S, &data[..len]);
+            let string = Codex32String::from_seed("ms", 0, "leet", Fe::S, &data[..len]);
+            if (16..=44).contains(&len) || len >= 63 {
+                let (buf, string) = (buf.unwrap(), string.unwrap());
+                assert_eq!(buf.expose_secret(), string.expose_secret());
+                assert_eq!(&string.parts().data()[..], &data[..len]);
+            } else {
+                assert!(matches!(buf, Err(Error::InvalidLength(_))));
+                assert!(matches!(string, Err(Error::InvalidLength(_))));
+            }
+        }
+
+        // An HRP with a digit in it is upper case if any letter is, both
+        // when creating shares and when interpolating them
+        let shares = [
+            Codex32Buf::from_seed("MS2", 2, "LEET", Fe::A, &seed).unwrap(),
+            Codex32Buf::from_seed("MS2", 2, "LEET", Fe::C, &data[..16]).unwrap(),
+        ];
+        let secret = Codex32Buf::interpolate_at(&shares, Fe::S).unwrap();
+        let expected = secret.expose_secret().to_ascii_uppercase();
+        assert_eq!(secret.expose_secret(), expected);
+        assert!(secret.expose_secret().parse::<Codex32Buf>().is_ok());
+
+        assert!(matches!(
+            Codex32Buf::from_seed("mss", 0, "leet", Fe::S, &data),
+            Err(Error::InvalidLength(128)),
+        ));
+        assert!(matches!(
+            Codex32Buf::from_seed("ms", 0, "leet", Fe::A, &seed),
+            Err(Error::InvalidShareIndex(Fe::A)),
+        ));
+        assert!(matches!(
+            Codex32String::from_seed("ms", 0, "leet", Fe::A, &seed),
+            Err(Error::InvalidShareIndex(Fe::A)),
+        ));
+    }
+}
@@ -19,67 +19,134 @@
 
 use super::{Case, Error};
 use crate::field::Fe;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+/// Maximum length of a checksum residue, i.e. that of the long checksum
+pub const MAX_RESIDUE_LEN: usize = 15;
+
+/// Generator of the normal codex32 checksum
+#[rustfmt::skip]
+const SHORT_GENERATOR: [Fe; 13] = [
+    Fe::E, Fe::M, Fe::_3, Fe::G, Fe::Q, Fe::E,
+    Fe::E, Fe::E, Fe::L, Fe::M, Fe::C, Fe::S,
+    Fe::S,
+];
+
+/// Residue of a valid string under the normal codex32 checksum
+#[rustfmt::skip]
+const SHORT_TARGET: [Fe; 13] = [
+    Fe::S, Fe::E, Fe::C, Fe::R, Fe::E, Fe::T,
+    Fe::S, Fe::H, Fe::A, Fe::R, Fe::E, Fe::_3,
+    Fe::_2,
+];
+
+/// Generator of the "long" codex32 checksum
+// hyk9x4hx4ef6e20p
+#[rustfmt::skip]
+const LONG_GENERATOR: [Fe; 15] = [
+    Fe::_0, Fe::_2, Fe::E, Fe::_6, Fe::F, Fe::E,
+    Fe::_4, Fe::X, Fe::H, Fe::_4, Fe::X, Fe::_9,
+    Fe::K,  Fe::Y, Fe::H,
+];
+
+/// Residue of a valid string under the "long" codex32 checksum
+#[rustfmt::skip]
+const LONG_TARGET: [Fe; 15] = [
+    Fe::S, Fe::E, Fe::C, Fe::R, Fe::E, Fe::T,
+    Fe::S, Fe::H, Fe::A, Fe::R, Fe::E, Fe::_3,
+    Fe::_2, Fe::E, Fe::X,
+];
+
+/// Multiples of the generator of the normal codex32 checksum by each field
+/// element, indexed by numeric value
+pub const SHORT_GENERATOR_TABLE: [[Fe; MAX_RESIDUE_LEN]; 32] = generator_table(&SHORT_GENERATOR);
+
+/// Multiples of the generator of the "long" codex32 checksum by each field
+/// element, indexed by numeric value
+pub const LONG_GENERATOR_TABLE: [[Fe; MAX_RESIDUE_LEN]; 32] = generator_table(&LONG_GENERATOR);
+
+/// Computes the multiples of a generator by each field element, indexed by
+/// numeric value and padded with zeroes to the maximum residue length.
+///
+/// With this table, reducing a residue modulo the generator is a single
+/// lookup rather than a row of multiplications. This is a `const fn` so that
+/// the table can be computed at compile time and stored in ROM.
+pub const fn generator_table(generator: &[Fe]) -> [[Fe; MAX_RESIDUE_LEN]; 32] {
+    let mut ret = [[Fe::Q; MAX_RESIDUE_LEN]; 32];
+    let mut x = 0;
+    while x < 32 {
+        let mut i = 0;
+        while i < generator.len() {
+            ret[x][i] = generator[i].ct_mul(Fe::from_low_bits(x as u8));
+            i += 1;
+        }
+        x += 1;
+    }
+    ret
+}
 
 /// An engine which consumes one GF32 character at a time, and produces
 /// a residue modulo some generator
-#[derive(Clone, PartialEq, Eq, Debug)]
+///
+/// The engine never allocates.
+#[derive(Clone, PartialEq, Eq)]
 pub struct Engine {
     case: Option<Case>,
-    generator: Vec<Fe>,
-    residue: Vec<Fe>,
-    target: Vec<Fe>,
+    generator: &'static [Fe],
+    table: &'static [[Fe; MAX_RESIDUE_LEN]; 32],
+    residue: [Fe; MAX_RESIDUE_LEN],
+    target: &'static [Fe],
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Engine {
+    fn drop(&mut self) {
+        for fe in self.residue.iter_mut() {
+            fe.zeroize();
+        }
+    }
+}
+
+impl fmt::Debug for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Engine")
+            .field("case", &self.case)
+            .field("residue_len", &self.generator.len())
+            .field("residue", &"<redacted>")
+            .finish()
+    }
 }
 
 impl Engine {
-    // An engine which computes the normal codex32 checksum
-    pub fn new_codex32_short() -> Engine {
+    /// Creates an engine with the given generator, its table of multiples,
+    /// and target, and the initial residue 1
+    fn new(
+        generator: &'static [Fe],
+        table: &'static [[Fe; MAX_RESIDUE_LEN]; 32],
+        target: &'static [Fe],
+    ) -> Engine {
+        let mut residue = [Fe::Q; MAX_RESIDUE_LEN];
+        residue[generator.len() - 1] = Fe::P;
         Engine {
             case: None,
-            #[rustfmt::skip]
-            generator: vec![
-                Fe::E, Fe::M, Fe::_3, Fe::G, Fe::Q, Fe::E,
-                Fe::E, Fe::E, Fe::L, Fe::M, Fe::C, Fe::S,
-                Fe::S,
-            ],
-            #[rustfmt::skip]
-            residue: vec![
-                Fe::Q, Fe::Q, Fe::Q, Fe::Q, Fe::Q, Fe::Q,
-                Fe::Q, Fe::Q, Fe::Q, Fe::Q, Fe::Q, Fe::Q,
-                Fe::P,
-            ],
-            #[rustfmt::skip]
-            target: vec![
-                Fe::S, Fe::E, Fe::C, Fe::R, Fe::E, Fe::T,
-                Fe::S, Fe::H, Fe::A, Fe::R, Fe::E, Fe::_3,
-                Fe::_2,
-            ],
+            generator,
+            table,
+            residue,
+            target,
         }
     }
 
+    // An engine which computes the normal codex32 checksum
+    pub fn new_codex32_short() -> Engine {
+        Engine::new(&SHORT_GENERATOR, &SHORT_GENERATOR_TABLE, &SHORT_TARGET)
+    }
+
     // An engine which computes the "long" codex32 checksum
     pub fn new_codex32_long() -> Engine {
-        // hyk9x4hx4ef6e20p
-        Engine {
-            case: None,
-            #[rustfmt::skip]
-            generator: vec![
-                Fe::_0, Fe::_2, Fe::E, Fe::_6, Fe::F, Fe::E,
-                Fe::_4, Fe::X, Fe::H, Fe::_4, Fe::X, Fe::_9,
-                Fe::K,  Fe::Y, Fe::H,
-            ],
-            #[rustfmt::skip]
-            residue: vec![
-                Fe::Q, Fe::Q, Fe::Q, Fe::Q, Fe::Q, Fe::Q,
-                Fe::Q, Fe::Q, Fe::Q, Fe::Q, Fe::Q, Fe::Q,
-                Fe::Q, Fe::Q, Fe::P,
-            ],
-            #[rustfmt::skip]
-            target: vec![
-                Fe::S, Fe::E, Fe::C, Fe::R, Fe::E, Fe::T,
-                Fe::S, Fe::H, Fe::A, Fe::R, Fe::E, Fe::_3,
-                Fe::_2, Fe::E, Fe::X,
-            ],
-        }
+        Engine::new(&LONG_GENERATOR, &LONG_GENERATOR_TABLE, &LONG_TARGET)
     }
 
     /// When computing checksums of "diffs" you do may want to set
@@ -88,12 +155,25 @@ impl Engine {
     /// If you do not know exactly why you are using this function,
     /// you should not use it.
     pub fn force_residue_to_zero(&mut self) {
-        self.residue = vec![Fe::Q; self.residue.len()];
+        for fe in self.residue.iter_mut() {
+            *fe = Fe::Q;
+        }
     }
 
     /// Extracts the residue from a checksum engine
     pub fn into_residue(self) -> Vec<Fe> {
-        self.residue
+        self.residue().to_vec()
+    }
+
+    /// Borrows the current residue of the checksum engine, highest-degree
+    /// coefficient first
+    pub fn residue(&self) -> &[Fe] {
+        &self.residue[..self.generator.len()]
+    }
+
+    /// Borrows the target residue, i.e. the residue of a valid string
+    pub fn target(&self) -> &[Fe] {
+        self.target
     }
 
     /// Determines whether the residue matches the target value
@@ -103,18 +183,25 @@ impl Engine {
     /// call the `into_residue` function (which will consume the
     /// engine).
     pub fn is_valid(&self) -> bool {
-        self.residue == self.target
+        // Fold the differences together and compare once, rather than
+        // stopping at the first difference in the (secret) residue
+        let mut diff = 0;
+        for (res, target) in self.residue().iter().zip(self.target) {
+            diff |= res.ct_add(*target).to_u8();
+        }
+        diff == 0
     }
 
     /// Initializes the checksum engine by loading an HRP into it
     pub fn input_hrp(&mut self, hrp: &str) -> Result<(), Error> {
+        // The HRP is public, so we can use the faster variable-time lookup
         for ch in hrp.chars() {
             self.set_check_case(ch)?;
-            self.input_fe(Fe::from_int(u32::from(ch.to_ascii_lowercase()) >> 5)?);
+            self.input_public_fe(Fe::from_int(u32::from(ch.to_ascii_lowercase()) >> 5)?);
         }
-        self.input_fe(Fe::Q);
+        self.input_public_fe(Fe::Q);
         for ch in hrp.chars() {
-            self.input_fe(Fe::from_int(u32::from(ch.to_ascii_lowercase()) & 0x1f)?);
+            self.input_public_fe(Fe::from_int(u32::from(ch.to_ascii_lowercase()) & 0x1f)?);
         }
         Ok(())
     }
@@ -137,9 +224,8 @@ impl Engine {
 
     /// Adds the target residue to the end of the input string
     pub fn input_own_target(&mut self) {
-        let fuck_the_borrow_checker = self.target.clone();
-        for u in fuck_the_borrow_checker {
-            self.input_fe(u);
+        for u in self.target {
+            self.input_fe(*u);
         }
     }
 
@@ -174,12 +260,31 @@ impl Engine {
         self.case
     }
 
+    /// Adds a single field element, which must not be secret, to the
+    /// checksum engine
+    ///
+    /// This reduces by looking up the multiple of the generator in a table,
+    /// indexed by the outgoing coefficient of the residue, which may leak
+    /// the residue through cache timing.
+    fn input_public_fe(&mut self, e: Fe) {
+        let res_len = self.generator.len();
+        let xn = self.residue[0];
+        for i in 1..res_len {
+            self.residue[i - 1] = self.residue[i];
+        }
+        self.residue[res_len - 1] = e;
+        let row = &self.table[usize::from(xn.to_u8())];
+        for (res, mult) in self.residue[..res_len].iter_mut().zip(row.iter()) {
+            *res += *mult;
+        }
+    }
+
     /// Adds a single field element to the checksum engine
     ///
     /// This is where the real magic happens.
     #[rustfmt::skip]
     pub fn input_fe(&mut self, e: Fe) {
-        let res_len = self.residue.len(); // needed for borrowck
+        let res_len = self.generator.len();
         // Store current coefficient of x^{n-1}, which will become
         // x^n (and get reduced)
         let xn = self.residue[0];
@@ -188,9 +293,69 @@ impl Engine {
             self.residue[i - 1] = self.residue[i];
         }
         self.residue[res_len - 1] = e;
-        // Then reduce x^n mod the generator.
+        // Then reduce x^n mod the generator. The residue depends on the (possibly
+        // secret) input, so use constant-time multiplication.
         for i in 0..res_len {
-            self.residue[i] += self.generator[i] * xn;
+            self.residue[i] += self.generator[i].ct_mul(xn);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check_string, Codex32String};
+    use alloc::collections::BTreeMap;
+    use alloc::string::String;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::sample::Index;
+
+    proptest! {
+        #[test]
+        fn detects_substitutions(
+            seed in vec(any::<u8>(), 16..=44),
+            errors in vec((any::<Index>(), 1u8..32), 1..=4),
+        ) {
+            let valid = Codex32String::from_seed("ms", 0, "leet", Fe::S, &seed).unwrap();
+            let valid = valid.expose_secret();
+            prop_assert!(valid.len() <= 93); // so uses the short checksum
+
+            // Add a nonzero error to each of up to four distinct characters
+            // of the data part
+            let data_start = "ms1".len();
+            let errors: BTreeMap<usize, u8> = errors
+                .into_iter()
+                .map(|(idx, err)| (data_start + idx.index(valid.len() - data_start), err))
+                .collect();
+            let corrupted: String = valid
+                .char_indices()
+                .map(|(i, ch)| match errors.get(&i) {
+                    Some(&err) => (Fe::from_char(ch).unwrap() + Fe::from_u8(err).unwrap()).to_char(),
+                    None => ch,
+                })
+                .collect();
+            prop_assert!(
+                matches!(check_string(&corrupted), Err(Error::InvalidChecksum { .. })),
+                "undetected errors at {:?}",
+                errors.keys().collect::<alloc::vec::Vec<_>>(),
+            );
+        }
+    }
+
+    #[test]
+    fn generator_tables() {
+        for (generator, table) in &[
+            (&SHORT_GENERATOR[..], &SHORT_GENERATOR_TABLE),
+            (&LONG_GENERATOR[..], &LONG_GENERATOR_TABLE),
+        ] {
+            for (x, row) in table.iter().enumerate() {
+                let fe = Fe::from_u8(x as u8).unwrap();
+                for (i, mult) in row.iter().enumerate() {
+                    let expected = generator.get(i).map(|g| *g * fe).unwrap_or(Fe::Q);
+                    assert_eq!(*mult, expected);
+                }
+            }
         }
     }
 }
@@ -0,0 +1,207 @@
+// Rust Codex32 Library and Reference Implementation
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Error Correction
+//!
+//! Corrects a single mistyped character in a codex32 string. The codex32
+//! checksum can in principle correct up to four substitutions, but this
+//! module only handles the most common case of one, by trying every
+//! possible substitution in turn.
+//!
+
+use alloc::string::String;
+
+use super::{check_string, checksum, Codex32String, Error};
+use crate::field::Fe;
+
+impl Codex32String {
+    /// Construct a codex32 string from a string which may have a single
+    /// substituted character in its data part, correcting it.
+    ///
+    /// Returns the corrected string along with the byte position of the
+    /// corrected character, or `None` if the string was already valid. A
+    /// character outside of the bech32 alphabet, including a stray `1`, is
+    /// treated as a substitution at a known position. The HRP is assumed
+    /// not to contain the separator `1`.
+    ///
+    /// Since the checksum guarantees that any two valid strings differ in at
+    /// least 8 characters, the correction is unique. However, a string with
+    /// many errors may be "corrected" to an unrelated valid string, so any
+    /// correction should be confirmed by the user.
+    pub fn from_string_corrected(s: String) -> Result<(Codex32String, Option<usize>), Error> {
+        // Wrap the input first, so that it is wiped if it cannot be corrected
+        let mut ret = Codex32String(s);
+        let correction = find_substitution(&ret.0)?;
+        if let Some((pos, ch)) = correction {
+            // The replacement is ASCII for ASCII, so this does not reallocate
+            ret.0
+                .replace_range(pos..pos + 1, ch.encode_utf8(&mut [0; 4]));
+            check_string(&ret.0).map_err(|_| Error::Uncorrectable)?;
+        }
+        Ok((ret, correction.map(|(pos, _)| pos)))
+    }
+
+    /// Like [`Codex32String::from_string_corrected`], but reads a borrowed
+    /// string, so that the only copy made is of the corrected result.
+    pub fn from_str_corrected(s: &str) -> Result<(Codex32String, Option<usize>), Error> {
+        let correction = find_substitution(s)?;
+        let mut ret = Codex32String(String::with_capacity(s.len()));
+        for (pos, ch) in s.char_indices() {
+            match correction {
+                Some((bad, new)) if bad == pos => ret.0.push(new),
+                _ => ret.0.push(ch),
+            }
+        }
+        if correction.is_some() {
+            check_string(&ret.0).map_err(|_| Error::Uncorrectable)?;
+        }
+        Ok((ret, correction.map(|(pos, _)| pos)))
+    }
+}
+
+/// Finds a single substitution which corrects a codex32 string, returning
+/// its position and the correct character, or `None` if the string was
+/// already valid
+fn find_substitution(s: &str) -> Result<Option<(usize, char)>, Error> {
+    match check_string(s) {
+        Ok(()) => return Ok(None),
+        Err(Error::InvalidChecksum { .. }) | Err(Error::InvalidChar(_)) => {}
+        Err(e) => return Err(e),
+    }
+    if let Some(ch) = s.chars().find(|ch| !ch.is_ascii()) {
+        return Err(Error::InvalidChar(ch));
+    }
+    let data_start = match s.find('1') {
+        Some(sep) => sep + 1,
+        None => return Err(Error::Uncorrectable),
+    };
+    let upper = s.bytes().any(|b| b.is_ascii_uppercase());
+
+    // If there is a character which is not bech32 at all, it must be the
+    // one to correct.
+    let mut positions = data_start..s.len();
+    let mut erasures = s[data_start..]
+        .char_indices()
+        .filter(|(_, ch)| Fe::from_char(*ch).is_err())
+        .map(|(pos, _)| data_start + pos);
+    if let Some(pos) = erasures.next() {
+        if erasures.next().is_some() {
+            return Err(Error::Uncorrectable);
+        }
+        positions = pos..pos + 1;
+    }
+
+    for pos in positions {
+        // unwrap ok since the string is ASCII
+        let original = s[pos..].chars().next().unwrap();
+        for fe in Fe::iter_alpha() {
+            let ch = if upper {
+                fe.to_char().to_ascii_uppercase()
+            } else {
+                fe.to_char()
+            };
+            if ch != original && is_valid_with(s, data_start, pos, ch) {
+                return Ok(Some((pos, ch)));
+            }
+        }
+    }
+    Err(Error::Uncorrectable)
+}
+
+/// Checks the checksum of `s` with the character at `pos` replaced by `ch`,
+/// without making a copy of it
+fn is_valid_with(s: &str, data_start: usize, pos: usize, ch: char) -> bool {
+    // The length was checked by `check_string` before we got here
+    let mut engine = if s.len() < 94 {
+        checksum::Engine::new_codex32_short()
+    } else {
+        checksum::Engine::new_codex32_long()
+    };
+    if engine.input_hrp(&s[..data_start - 1]).is_err() {
+        return false;
+    }
+    for (i, c) in s[data_start..].char_indices() {
+        let c = if data_start + i == pos { ch } else { c };
+        if engine.input_char(c).is_err() {
+            return false;
+        }
+    }
+    engine.is_valid()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    const VALID: &str = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw";
+
+    #[test]
+    fn correct() {
+        let (s, pos) = Codex32String::from_string_corrected(VALID.to_string()).unwrap();
+        assert_eq!(s.expose_secret(), VALID);
+        assert_eq!(pos, None);
+
+        for (pos, typo) in &[
+            (10, 'q'),
+            (20, 'l'),
+            (40, 'x'),
+            (4, 'e'),
+            (9, 'b'),
+            (30, '1'),
+        ] {
+            let mut bad = VALID.to_string();
+            bad.replace_range(*pos..*pos + 1, &typo.to_string());
+            let (s, fixed) = Codex32String::from_str_corrected(&bad).unwrap();
+            assert_eq!(s.expose_secret(), VALID);
+            assert_eq!(fixed, Some(*pos));
+            let (s, fixed) = Codex32String::from_string_corrected(bad).unwrap();
+            assert_eq!(s.expose_secret(), VALID);
+            assert_eq!(fixed, Some(*pos));
+        }
+
+        let upper = VALID.to_ascii_uppercase();
+        let mut bad = upper.clone();
+        bad.replace_range(15..16, "Q");
+        let (s, fixed) = Codex32String::from_string_corrected(bad).unwrap();
+        assert_eq!(s.expose_secret(), upper);
+        assert_eq!(fixed, Some(15));
+    }
+
+    #[test]
+    fn uncorrectable() {
+        let mut bad = VALID.to_string();
+        bad.replace_range(10..12, "qq");
+        assert!(matches!(
+            Codex32String::from_str_corrected(&bad),
+            Err(Error::Uncorrectable),
+        ));
+        assert!(matches!(
+            Codex32String::from_string_corrected(bad),
+            Err(Error::Uncorrectable),
+        ));
+
+        let mut bad = VALID.to_string();
+        bad.replace_range(10..12, "bb");
+        assert!(matches!(
+            Codex32String::from_string_corrected(bad),
+            Err(Error::Uncorrectable),
+        ));
+
+        assert!(matches!(
+            Codex32String::from_string_corrected("ms10test".to_string()),
+            Err(Error::InvalidLength(8)),
+        ));
+    }
+}
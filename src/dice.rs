@@ -0,0 +1,328 @@
+// Rust Codex32 Library and Reference Implementation
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Dice
+//!
+//! Produces master seeds from physical randomness: dice rolls or coin flips.
+//!
+//! Real dice and coins are biased, so the rolls are not used directly.
+//! Instead they are debiased with von Neumann's procedure: rolls are taken
+//! in consecutive pairs; if the two rolls of a pair are equal the pair is
+//! discarded, and otherwise it produces a 0 bit if the first roll is lower
+//! and a 1 bit if it is higher. As long as the rolls are independent, both
+//! orders are exactly equally likely however the die is weighted, so the
+//! output bits are unbiased. The procedure is simple enough to be checked
+//! by hand.
+//!
+
+use alloc::{string::ToString, vec::Vec};
+use core::fmt;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+use super::{Codex32String, Error, SecretSeed};
+use crate::field::Fe;
+
+/// A source of physical randomness
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Die {
+    /// A coin, written `H` or `T`
+    Coin,
+    /// A six-sided die, written `1` through `6`
+    D6,
+    /// A twenty-sided die, written `1` through `20`
+    D20,
+}
+
+impl Die {
+    /// The number of sides of the die
+    pub fn sides(self) -> u8 {
+        match self {
+            Die::Coin => 2,
+            Die::D6 => 6,
+            Die::D20 => 20,
+        }
+    }
+
+    /// The expected number of rolls of a fair die needed to produce `bits`
+    /// bits of debiased entropy, rounded up.
+    ///
+    /// Each pair of rolls produces a bit unless the two rolls are equal, which
+    /// happens with probability 1/sides for a fair die. Biased dice will need
+    /// more rolls than this.
+    pub fn expected_rolls(self, bits: usize) -> usize {
+        let sides = usize::from(self.sides());
+        (2 * bits * sides + sides - 2) / (sides - 1)
+    }
+
+    /// Parses a single written observation of the die into a roll, from 1
+    /// up to the number of sides.
+    fn parse_roll(self, s: &str) -> Result<u8, Error> {
+        let roll = match self {
+            Die::Coin => match s {
+                "h" | "H" => Some(1),
+                "t" | "T" => Some(2),
+                _ => None,
+            },
+            // Only accept plain decimal numbers; in particular reject a leading
+            // `+` or `0`, which `str::parse` would allow, in case they indicate
+            // a different (e.g. zero-based) labelling of the die.
+            Die::D6 | Die::D20 => {
+                if s.starts_with('0') || !s.bytes().all(|b| b.is_ascii_digit()) {
+                    None
+                } else {
+                    s.parse().ok()
+                }
+            }
+        };
+        match roll {
+            Some(roll) if roll >= 1 && roll <= self.sides() => Ok(roll),
+            _ => Err(Error::InvalidDieRoll(s.into())),
+        }
+    }
+}
+
+/// The length of the longest master seed allowed by BIP-93, in bytes
+const MAX_SEED_LEN: usize = 64;
+
+/// Accumulates debiased entropy from a sequence of rolls of a die
+#[derive(Clone, PartialEq, Eq)]
+pub struct DiceEntropy {
+    die: Die,
+    pending: Option<u8>,
+    n_rolls: usize,
+    n_bits: usize,
+    bytes: Vec<u8>,
+}
+
+impl fmt::Debug for DiceEntropy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DiceEntropy")
+            .field("die", &self.die)
+            .field("n_rolls", &self.n_rolls)
+            .field("n_bits", &self.n_bits)
+            .field("entropy", &"<redacted>")
+            .finish()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for DiceEntropy {
+    fn drop(&mut self) {
+        self.pending.zeroize();
+        self.bytes.zeroize();
+    }
+}
+
+impl DiceEntropy {
+    /// Creates a new, empty, entropy accumulator for the given die
+    pub fn new(die: Die) -> DiceEntropy {
+        DiceEntropy {
+            die,
+            pending: None,
+            n_rolls: 0,
+            n_bits: 0,
+            // Never grown past this, so no unwiped copies are left behind
+            bytes: Vec::with_capacity(MAX_SEED_LEN),
+        }
+    }
+
+    /// The number of rolls entered so far
+    pub fn n_rolls(&self) -> usize {
+        self.n_rolls
+    }
+
+    /// The number of debiased bits extracted so far
+    ///
+    /// Bits beyond the 512 needed for the longest seed are discarded, and not
+    /// counted.
+    pub fn n_bits(&self) -> usize {
+        self.n_bits
+    }
+
+    /// Adds a single roll, numbered from 1 up to the number of sides of the
+    /// die. For a coin, heads is 1 and tails is 2.
+    pub fn add_roll(&mut self, roll: u8) -> Result<(), Error> {
+        if roll < 1 || roll > self.die.sides() {
+            return Err(Error::InvalidDieRoll(roll.to_string()));
+        }
+        self.n_rolls += 1;
+        match self.pending.take() {
+            None => self.pending = Some(roll),
+            Some(first) if first == roll => {}
+            Some(_) if self.n_bits == 8 * MAX_SEED_LEN => {}
+            Some(first) => {
+                if self.n_bits % 8 == 0 {
+                    self.bytes.push(0);
+                }
+                if first > roll {
+                    let last = self.bytes.len() - 1;
+                    self.bytes[last] |= 0x80 >> (self.n_bits % 8);
+                }
+                self.n_bits += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a sequence of written rolls, separated by whitespace or commas.
+    ///
+    /// If any roll is invalid, none of the rolls are added.
+    pub fn add_rolls_str(&mut self, s: &str) -> Result<(), Error> {
+        let rolls = s
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|tok| !tok.is_empty())
+            .map(|tok| self.die.parse_roll(tok))
+            .collect::<Result<Vec<u8>, Error>>()?;
+        for roll in rolls {
+            self.add_roll(roll)?;
+        }
+        Ok(())
+    }
+
+    /// Outputs a master seed of `len` bytes, taken from the first `8 * len`
+    /// debiased bits.
+    pub fn into_seed(self, len: usize) -> Result<SecretSeed, Error> {
+        if !(16..=MAX_SEED_LEN).contains(&len) {
+            return Err(Error::InvalidSeedLength(len));
+        }
+        if self.n_bits < 8 * len {
+            return Err(Error::NotEnoughEntropy {
+                needed: 8 * len,
+                available: self.n_bits,
+            });
+        }
+        Ok(SecretSeed(self.bytes[..len].to_vec()))
+    }
+
+    /// Outputs the `S` share of a master seed of `len` bytes, taken from the
+    /// first `8 * len` debiased bits.
+    pub fn into_share(
+        self,
+        hrp: &str,
+        threshold: usize,
+        id: &str,
+        len: usize,
+    ) -> Result<Codex32String, Error> {
+        let seed = self.into_seed(len)?;
+        Codex32String::from_seed(hrp, threshold, id, Fe::S, &seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expected_rolls() {
+        assert_eq!(Die::Coin.expected_rolls(128), 512);
+        assert_eq!(Die::Coin.expected_rolls(256), 1024);
+        assert_eq!(Die::D6.expected_rolls(128), 308);
+        assert_eq!(Die::D6.expected_rolls(256), 615);
+        assert_eq!(Die::D20.expected_rolls(128), 270);
+        assert_eq!(Die::D20.expected_rolls(256), 539);
+    }
+
+    #[test]
+    fn debiasing() {
+        let mut ent = DiceEntropy::new(Die::D6);
+        // 1 < 6 gives 0, 5 = 5 is discarded, 4 > 2 gives 1
+        ent.add_rolls_str("1 6, 5 5, 4 2").unwrap();
+        assert_eq!(ent.n_rolls(), 6);
+        assert_eq!(ent.n_bits(), 2);
+        assert_eq!(ent.bytes, vec![0b0100_0000]);
+
+        // A single unpaired roll produces nothing
+        ent.add_roll(3).unwrap();
+        assert_eq!(ent.n_bits(), 2);
+        ent.add_roll(1).unwrap();
+        assert_eq!(ent.n_bits(), 3);
+        assert_eq!(ent.bytes, vec![0b0110_0000]);
+
+        // A die that always gives the same number produces nothing at all
+        let mut ent = DiceEntropy::new(Die::D20);
+        for _ in 0..1000 {
+            ent.add_roll(17).unwrap();
+        }
+        assert_eq!(ent.n_bits(), 0);
+    }
+
+    #[test]
+    fn no_reallocation() {
+        // Bits past the longest seed are dropped rather than growing the buffer
+        let mut ent = DiceEntropy::new(Die::Coin);
+        let cap = ent.bytes.capacity();
+        assert!(cap >= MAX_SEED_LEN);
+        for _ in 0..2000 {
+            ent.add_rolls_str("H T").unwrap();
+        }
+        assert_eq!(ent.n_bits(), 8 * MAX_SEED_LEN);
+        assert_eq!(ent.bytes.len(), MAX_SEED_LEN);
+        assert_eq!(ent.bytes.capacity(), cap);
+        assert!(ent.into_seed(MAX_SEED_LEN).is_ok());
+    }
+
+    #[test]
+    fn rolls_for_seed() {
+        // With no repeated pairs, 2 rolls per bit are needed
+        for &(len, rolls) in &[(16, 256), (32, 512)] {
+            let mut ent = DiceEntropy::new(Die::Coin);
+            for i in 0..rolls - 1 {
+                ent.add_roll([1, 2, 2, 1][i % 4]).unwrap();
+            }
+            assert!(matches!(
+                ent.clone().into_seed(len),
+                Err(Error::NotEnoughEntropy { .. }),
+            ));
+            ent.add_rolls_str("H").unwrap();
+            let seed = ent.clone().into_seed(len).unwrap();
+            assert_eq!(&seed[..], &vec![0x55; len][..]);
+
+            let share = ent.into_share("ms", 0, "dyce", len).unwrap();
+            assert_eq!(share.parts().data(), seed);
+        }
+    }
+
+    #[test]
+    fn reject_bad_rolls() {
+        let mut ent = DiceEntropy::new(Die::D6);
+        for bad in &["0", "7", "06", "+3", "-1", "h", "1.0", "six"] {
+            assert!(matches!(
+                ent.add_rolls_str(bad),
+                Err(Error::InvalidDieRoll(..)),
+            ));
+        }
+        assert!(matches!(ent.add_roll(0), Err(Error::InvalidDieRoll(..))));
+        // Nothing is added if any roll in a sequence is bad
+        assert!(ent.add_rolls_str("1 2 3 4 5 6 7").is_err());
+        assert_eq!(ent.n_rolls(), 0);
+
+        let mut ent = DiceEntropy::new(Die::D20);
+        assert!(ent.add_rolls_str("0").is_err());
+        assert!(ent.add_rolls_str("21").is_err());
+        ent.add_rolls_str("20 1 19").unwrap();
+        assert_eq!(ent.n_rolls(), 3);
+
+        let mut ent = DiceEntropy::new(Die::Coin);
+        assert!(ent.add_rolls_str("1").is_err());
+        assert!(ent.add_rolls_str("heads").is_err());
+        ent.add_rolls_str("H t h T").unwrap();
+        assert_eq!(ent.n_bits(), 2);
+
+        assert!(matches!(
+            DiceEntropy::new(Die::Coin).into_seed(8),
+            Err(Error::InvalidSeedLength(8)),
+        ));
+    }
+}
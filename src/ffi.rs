@@ -0,0 +1,649 @@
+// Rust Codex32 Library and Reference Implementation
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! C Bindings
+//!
+//! `extern "C"` functions for parsing, creating, splitting, recovering and
+//! correcting codex32 strings from C and C++. The header `include/codex32.h`
+//! is generated from this module by `cbindgen`, and `contrib/ffi-test.c`
+//! exercises it.
+//!
+//! Strings are passed around as opaque `codex32_string` handles, which must
+//! be released with [`codex32_string_free`]. Since this feature enables
+//! `zeroize`, freeing a handle wipes the string from memory. Every function
+//! which can fail returns a [`Codex32Error`], with [`Codex32Error::Ok`] on
+//! success, and only writes to its output parameters on success.
+//!
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::{ptr, slice, str};
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+use zeroize::Zeroize;
+
+use super::{check_string, Codex32String, Error};
+use crate::field::Fe;
+
+/// An opaque handle to a valid codex32 string
+#[allow(non_camel_case_types)]
+pub struct codex32_string(Codex32String);
+
+/// Result of a call into the library, corresponding to the variants of the
+/// Rust library's `Error`, or to problems with the arguments themselves
+///
+/// The numeric values are part of the ABI and will not change.
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Codex32Error {
+    /// Success
+    Ok = 0,
+    /// A required pointer argument was null
+    NullPointer = 1,
+    /// A string argument was not valid UTF-8
+    InvalidUtf8 = 2,
+    /// A character was not a valid bech32 character
+    Field = 3,
+    /// An identifier was not four characters long
+    IdNotLength4 = 4,
+    /// A payload ended in an entirely extraneous character
+    IncompleteGroup = 5,
+    /// A codex32 string had an illegal length
+    InvalidLength = 6,
+    /// A character was not in the bech32 alphabet, or was not ASCII
+    InvalidChar = 7,
+    /// A string mixed upper and lower case
+    InvalidCase = 8,
+    /// A string had an invalid checksum
+    InvalidChecksum = 9,
+    /// A threshold character was not 0 or 2 through 9
+    InvalidThreshold = 10,
+    /// A threshold was not 0 or 2 through 9
+    InvalidThresholdN = 11,
+    /// A share index was not allowed (only `s` is, for a threshold of 0)
+    InvalidShareIndex = 12,
+    /// A set of shares did not all have the same length
+    MismatchedLength = 13,
+    /// A set of shares did not all have the same HRP
+    MismatchedHrp = 14,
+    /// A set of shares did not all have the same threshold
+    MismatchedThreshold = 15,
+    /// A set of shares did not all have the same identifier
+    MismatchedId = 16,
+    /// A set of re-sharing contributions did not all have the same share index
+    MismatchedShareIndex = 17,
+    /// Shares were refreshed without changing their identifier
+    UnchangedId = 18,
+    /// A share index was repeated in a set of shares
+    RepeatedIndex = 19,
+    /// A share's index was not among those declared up front
+    UnexpectedIndex = 20,
+    /// A set of shares had fewer shares than its threshold
+    ThresholdNotPassed = 21,
+    /// More shares were requested than there are share indices
+    TooManyShares = 22,
+    /// A seed was not 16 to 64 bytes long
+    InvalidSeedLength = 23,
+    /// A die roll was not a valid observation of that die
+    InvalidDieRoll = 24,
+    /// Not enough entropy was given for a seed of the requested length
+    NotEnoughEntropy = 25,
+    /// An output buffer was too small
+    BufferTooSmall = 26,
+    /// The BIP-32 master key derived from a seed was invalid
+    InvalidMasterKey = 27,
+    /// A string could not be corrected by substituting a single character
+    Uncorrectable = 28,
+    /// A string had no separator `1` between its HRP and data part
+    MissingSeparator = 29,
+}
+
+impl From<Error> for Codex32Error {
+    fn from(e: Error) -> Codex32Error {
+        match e {
+            Error::Field(..) => Codex32Error::Field,
+            Error::IdNotLength4(..) => Codex32Error::IdNotLength4,
+            Error::IncompleteGroup(..) => Codex32Error::IncompleteGroup,
+            Error::InvalidLength(..) => Codex32Error::InvalidLength,
+            Error::InvalidChar(..) => Codex32Error::InvalidChar,
+            Error::InvalidCase(..) => Codex32Error::InvalidCase,
+            Error::InvalidChecksum { .. } => Codex32Error::InvalidChecksum,
+            Error::InvalidThreshold(..) => Codex32Error::InvalidThreshold,
+            Error::InvalidThresholdN(..) => Codex32Error::InvalidThresholdN,
+            Error::InvalidShareIndex(..) => Codex32Error::InvalidShareIndex,
+            Error::MismatchedLength(..) => Codex32Error::MismatchedLength,
+            Error::MismatchedHrp(..) => Codex32Error::MismatchedHrp,
+            Error::MismatchedThreshold(..) => Codex32Error::MismatchedThreshold,
+            Error::MismatchedId(..) => Codex32Error::MismatchedId,
+            Error::MismatchedShareIndex(..) => Codex32Error::MismatchedShareIndex,
+            Error::UnchangedId(..) => Codex32Error::UnchangedId,
+            Error::RepeatedIndex(..) => Codex32Error::RepeatedIndex,
+            Error::UnexpectedIndex(..) => Codex32Error::UnexpectedIndex,
+            Error::ThresholdNotPassed { .. } => Codex32Error::ThresholdNotPassed,
+            Error::TooManyShares(..) => Codex32Error::TooManyShares,
+            Error::InvalidSeedLength(..) => Codex32Error::InvalidSeedLength,
+            Error::InvalidDieRoll(..) => Codex32Error::InvalidDieRoll,
+            Error::NotEnoughEntropy { .. } => Codex32Error::NotEnoughEntropy,
+            Error::BufferTooSmall { .. } => Codex32Error::BufferTooSmall,
+            Error::InvalidMasterKey => Codex32Error::InvalidMasterKey,
+            Error::Uncorrectable => Codex32Error::Uncorrectable,
+            Error::MissingSeparator => Codex32Error::MissingSeparator,
+        }
+    }
+}
+
+/// Borrows a NUL-terminated C string as a `&str`
+unsafe fn borrow_str<'a>(s: *const c_char) -> Result<&'a str, Codex32Error> {
+    if s.is_null() {
+        return Err(Codex32Error::NullPointer);
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| Codex32Error::InvalidUtf8)
+}
+
+/// Borrows a pointer and length as a byte slice, allowing a null pointer
+/// for an empty slice
+unsafe fn borrow_bytes<'a>(data: *const u8, len: usize) -> Result<&'a [u8], Codex32Error> {
+    if len == 0 {
+        Ok(&[])
+    } else if data.is_null() {
+        Err(Codex32Error::NullPointer)
+    } else {
+        Ok(slice::from_raw_parts(data, len))
+    }
+}
+
+/// Clones an array of handles into a vector of strings
+unsafe fn clone_shares(
+    shares: *const *const codex32_string,
+    n_shares: usize,
+) -> Result<Vec<Codex32String>, Codex32Error> {
+    if shares.is_null() {
+        return Err(Codex32Error::NullPointer);
+    }
+    let mut ret = Vec::with_capacity(n_shares);
+    for share in slice::from_raw_parts(shares, n_shares) {
+        match share.as_ref() {
+            Some(share) => ret.push(share.0.clone()),
+            None => return Err(Codex32Error::NullPointer),
+        }
+    }
+    Ok(ret)
+}
+
+/// Moves a string into a new handle and writes it to `out`
+unsafe fn write_handle(out: *mut *mut codex32_string, s: Codex32String) {
+    *out = Box::into_raw(Box::new(codex32_string(s)));
+}
+
+/// Converts a bech32 character to a field element
+fn fe_from_c_char(ch: c_char) -> Result<Fe, Codex32Error> {
+    Fe::from_char(char::from(ch as u8)).map_err(Codex32Error::from)
+}
+
+/// Checks that a NUL-terminated string is a valid codex32 string, without
+/// copying it.
+///
+/// # Safety
+///
+/// `s` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn codex32_validate(s: *const c_char) -> Codex32Error {
+    match borrow_str(s) {
+        Ok(s) => match check_string(s) {
+            Ok(()) => Codex32Error::Ok,
+            Err(e) => e.into(),
+        },
+        Err(e) => e,
+    }
+}
+
+/// Parses a NUL-terminated string as a codex32 string, writing a new handle
+/// to `out`.
+///
+/// # Safety
+///
+/// `s` must be null or point to a NUL-terminated string, and `out` must be
+/// null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn codex32_parse(
+    s: *const c_char,
+    out: *mut *mut codex32_string,
+) -> Codex32Error {
+    let s = match borrow_str(s) {
+        Ok(s) => s,
+        Err(e) => return e,
+    };
+    if out.is_null() {
+        return Codex32Error::NullPointer;
+    }
+    // Validate before copying, so that no copy of an invalid string is left
+    // behind in freed memory.
+    if let Err(e) = check_string(s) {
+        return e.into();
+    }
+    write_handle(out, Codex32String(String::from(s)));
+    Codex32Error::Ok
+}
+
+/// Parses a NUL-terminated string as a codex32 string, correcting a single
+/// substituted character if necessary, and writes a new handle to `out`.
+///
+/// If a character was corrected, its position is written to
+/// `corrected_pos`; otherwise `SIZE_MAX` is. See
+/// `Codex32String::from_str_corrected` in the Rust library.
+///
+/// # Safety
+///
+/// `s` must be null or point to a NUL-terminated string, and `out` and
+/// `corrected_pos` must each be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn codex32_correct(
+    s: *const c_char,
+    out: *mut *mut codex32_string,
+    corrected_pos: *mut usize,
+) -> Codex32Error {
+    let s = match borrow_str(s) {
+        Ok(s) => s,
+        Err(e) => return e,
+    };
+    if out.is_null() || corrected_pos.is_null() {
+        return Codex32Error::NullPointer;
+    }
+    // As for `codex32_parse`, only the corrected result is ever copied.
+    match Codex32String::from_str_corrected(s) {
+        Ok((corrected, pos)) => {
+            write_handle(out, corrected);
+            *corrected_pos = pos.unwrap_or(usize::MAX);
+            Codex32Error::Ok
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Creates a codex32 string encoding `seed`, with the given HRP, threshold,
+/// identifier and share index, and writes a new handle to `out`.
+///
+/// `share_idx` is a single bech32 character, which must be `s` for a
+/// threshold of 0.
+///
+/// # Safety
+///
+/// `hrp` and `id` must each be null or point to a NUL-terminated string,
+/// `seed` must be valid for reads of `seed_len` bytes, and `out` must be
+/// null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn codex32_from_seed(
+    hrp: *const c_char,
+    threshold: usize,
+    id: *const c_char,
+    share_idx: c_char,
+    seed: *const u8,
+    seed_len: usize,
+    out: *mut *mut codex32_string,
+) -> Codex32Error {
+    let result = (|| {
+        let hrp = borrow_str(hrp)?;
+        let id = borrow_str(id)?;
+        let share_idx = fe_from_c_char(share_idx)?;
+        let seed = borrow_bytes(seed, seed_len)?;
+        if out.is_null() {
+            return Err(Codex32Error::NullPointer);
+        }
+        let s = Codex32String::from_seed(hrp, threshold, id, share_idx, seed)?;
+        write_handle(out, s);
+        Ok(())
+    })();
+    result.err().unwrap_or(Codex32Error::Ok)
+}
+
+/// Splits `seed` into `n_shares` shares, deterministically, and writes a
+/// new handle for each to the array `shares_out`.
+///
+/// See `Codex32String::split_deterministic` in the Rust library;
+/// `extra_entropy` may be null if `extra_entropy_len` is 0.
+///
+/// # Safety
+///
+/// `hrp` and `id` must each be null or point to a NUL-terminated string,
+/// `seed` and `extra_entropy` must be valid for reads of `seed_len` and
+/// `extra_entropy_len` bytes respectively, and `shares_out` must be null or
+/// valid for writes of `n_shares` handles.
+#[no_mangle]
+pub unsafe extern "C" fn codex32_split(
+    hrp: *const c_char,
+    threshold: usize,
+    id: *const c_char,
+    seed: *const u8,
+    seed_len: usize,
+    extra_entropy: *const u8,
+    extra_entropy_len: usize,
+    n_shares: usize,
+    shares_out: *mut *mut codex32_string,
+) -> Codex32Error {
+    let result = (|| {
+        let hrp = borrow_str(hrp)?;
+        let id = borrow_str(id)?;
+        let seed = borrow_bytes(seed, seed_len)?;
+        let extra_entropy = borrow_bytes(extra_entropy, extra_entropy_len)?;
+        if shares_out.is_null() {
+            return Err(Codex32Error::NullPointer);
+        }
+        let shares =
+            Codex32String::split_deterministic(hrp, threshold, id, seed, extra_entropy, n_shares)?;
+        for (i, share) in shares.into_iter().enumerate() {
+            write_handle(shares_out.add(i), share);
+        }
+        Ok(())
+    })();
+    result.err().unwrap_or(Codex32Error::Ok)
+}
+
+/// Interpolates an array of share handles at the index `target`, a single
+/// bech32 character, and writes a new handle to `out`.
+///
+/// Using the target `s` recovers the master seed as a codex32 string.
+///
+/// # Safety
+///
+/// `shares` must be null or valid for reads of `n_shares` handles, each of
+/// which must be null or have been returned by this library and not freed,
+/// and `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn codex32_interpolate(
+    shares: *const *const codex32_string,
+    n_shares: usize,
+    target: c_char,
+    out: *mut *mut codex32_string,
+) -> Codex32Error {
+    let result = (|| {
+        let shares = clone_shares(shares, n_shares)?;
+        let target = fe_from_c_char(target)?;
+        if out.is_null() {
+            return Err(Codex32Error::NullPointer);
+        }
+        let s = Codex32String::interpolate_at(&shares, target)?;
+        write_handle(out, s);
+        Ok(())
+    })();
+    result.err().unwrap_or(Codex32Error::Ok)
+}
+
+/// Recovers the master seed from an array of share handles, writing it to
+/// `seed_out` and its length to `seed_len`.
+///
+/// Errors with `CODEX32_ERROR_BUFFER_TOO_SMALL` if `seed_out_len` is less
+/// than the length of the seed, which is at most 64 bytes.
+///
+/// # Safety
+///
+/// `shares` must be null or valid for reads of `n_shares` handles, each of
+/// which must be null or have been returned by this library and not freed,
+/// `seed_out` must be null or valid for writes of `seed_out_len` bytes, and
+/// `seed_len` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn codex32_recover_seed(
+    shares: *const *const codex32_string,
+    n_shares: usize,
+    seed_out: *mut u8,
+    seed_out_len: usize,
+    seed_len: *mut usize,
+) -> Codex32Error {
+    let result = (|| {
+        let shares = clone_shares(shares, n_shares)?;
+        if seed_out.is_null() || seed_len.is_null() {
+            return Err(Codex32Error::NullPointer);
+        }
+        let secret = Codex32String::interpolate_at(&shares, Fe::S)?;
+        let out = slice::from_raw_parts_mut(seed_out, seed_out_len);
+        *seed_len = secret.parts().data_into(out)?;
+        Ok(())
+    })();
+    result.err().unwrap_or(Codex32Error::Ok)
+}
+
+/// The length of the string behind a handle, not including a NUL terminator,
+/// or 0 if `s` is null
+///
+/// # Safety
+///
+/// `s` must be null or have been returned by this library and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn codex32_string_len(s: *const codex32_string) -> usize {
+    s.as_ref().map_or(0, |s| s.0.expose_secret().len())
+}
+
+/// Copies the string behind a handle into `buf`, followed by a NUL
+/// terminator.
+///
+/// Errors with `CODEX32_ERROR_BUFFER_TOO_SMALL` if `buf_len` is not at
+/// least one more than `codex32_string_len`.
+///
+/// # Safety
+///
+/// `s` must be null or have been returned by this library and not freed,
+/// and `buf` must be null or valid for writes of `buf_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn codex32_string_copy(
+    s: *const codex32_string,
+    buf: *mut c_char,
+    buf_len: usize,
+) -> Codex32Error {
+    let s = match s.as_ref() {
+        Some(s) => s.0.expose_secret(),
+        None => return Codex32Error::NullPointer,
+    };
+    if buf.is_null() {
+        return Codex32Error::NullPointer;
+    }
+    if buf_len <= s.len() {
+        return Codex32Error::BufferTooSmall;
+    }
+    ptr::copy_nonoverlapping(s.as_ptr(), buf as *mut u8, s.len());
+    *buf.add(s.len()) = 0;
+    Codex32Error::Ok
+}
+
+/// Writes the payload of the string behind a handle, as bytes, to `out`,
+/// and its length to `len`.
+///
+/// For an `S` share, this is the master seed.
+///
+/// # Safety
+///
+/// `s` must be null or have been returned by this library and not freed,
+/// `out` must be null or valid for writes of `out_len` bytes, and `len`
+/// must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn codex32_string_data(
+    s: *const codex32_string,
+    out: *mut u8,
+    out_len: usize,
+    len: *mut usize,
+) -> Codex32Error {
+    let s = match s.as_ref() {
+        Some(s) => &s.0,
+        None => return Codex32Error::NullPointer,
+    };
+    if out.is_null() || len.is_null() {
+        return Codex32Error::NullPointer;
+    }
+    match s.parts().data_into(slice::from_raw_parts_mut(out, out_len)) {
+        Ok(n) => {
+            *len = n;
+            Codex32Error::Ok
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Frees a handle, wiping the string from memory. Does nothing if `s` is
+/// null.
+///
+/// # Safety
+///
+/// `s` must be null or have been returned by this library and not already
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn codex32_string_free(s: *mut codex32_string) {
+    if !s.is_null() {
+        drop(Box::from_raw(s));
+    }
+}
+
+/// Wipes a buffer, e.g. one filled by `codex32_string_copy`, in a way
+/// which will not be optimized away. Does nothing if `buf` is null.
+///
+/// # Safety
+///
+/// `buf` must be null or valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn codex32_wipe(buf: *mut u8, len: usize) {
+    if !buf.is_null() {
+        slice::from_raw_parts_mut(buf, len).zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn round_trip() {
+        unsafe {
+            let seed = [0x42; 16];
+            let hrp = CString::new("ms").unwrap();
+            let id = CString::new("leet").unwrap();
+            let mut shares = [ptr::null_mut(); 4];
+            let res = codex32_split(
+                hrp.as_ptr(),
+                3,
+                id.as_ptr(),
+                seed.as_ptr(),
+                seed.len(),
+                ptr::null(),
+                0,
+                shares.len(),
+                shares.as_mut_ptr(),
+            );
+            assert_eq!(res, Codex32Error::Ok);
+
+            let mut buf = [0 as c_char; 128];
+            let len = codex32_string_len(shares[1]);
+            assert_eq!(
+                codex32_string_copy(shares[1], buf.as_mut_ptr(), len),
+                Codex32Error::BufferTooSmall,
+            );
+            assert_eq!(
+                codex32_string_copy(shares[1], buf.as_mut_ptr(), buf.len()),
+                Codex32Error::Ok,
+            );
+            assert_eq!(codex32_validate(buf.as_ptr()), Codex32Error::Ok);
+            buf[20] = if buf[20] == b'q' as c_char {
+                b'p'
+            } else {
+                b'q'
+            } as c_char;
+            assert_eq!(
+                codex32_validate(buf.as_ptr()),
+                Codex32Error::InvalidChecksum
+            );
+            let mut corrected = ptr::null_mut();
+            let mut pos = 0;
+            assert_eq!(
+                codex32_correct(buf.as_ptr(), &mut corrected, &mut pos),
+                Codex32Error::Ok,
+            );
+            assert_eq!(pos, 20);
+            assert_eq!((*corrected).0, (*shares[1]).0);
+            codex32_string_free(corrected);
+            codex32_wipe(buf.as_mut_ptr() as *mut u8, buf.len());
+
+            let subset = [shares[3] as *const _, shares[0], shares[2]];
+            let mut recovered = [0; 64];
+            let mut recovered_len = 0;
+            assert_eq!(
+                codex32_recover_seed(
+                    subset.as_ptr(),
+                    subset.len(),
+                    recovered.as_mut_ptr(),
+                    recovered.len(),
+                    &mut recovered_len,
+                ),
+                Codex32Error::Ok,
+            );
+            assert_eq!(&recovered[..recovered_len], &seed[..]);
+            assert_eq!(
+                codex32_recover_seed(
+                    subset.as_ptr(),
+                    2,
+                    recovered.as_mut_ptr(),
+                    recovered.len(),
+                    &mut recovered_len,
+                ),
+                Codex32Error::ThresholdNotPassed,
+            );
+
+            let mut secret = ptr::null_mut();
+            assert_eq!(
+                codex32_from_seed(
+                    hrp.as_ptr(),
+                    3,
+                    id.as_ptr(),
+                    b's' as c_char,
+                    seed.as_ptr(),
+                    seed.len(),
+                    &mut secret,
+                ),
+                Codex32Error::Ok,
+            );
+            let mut interpolated = ptr::null_mut();
+            assert_eq!(
+                codex32_interpolate(subset.as_ptr(), 3, b's' as c_char, &mut interpolated),
+                Codex32Error::Ok,
+            );
+            assert_eq!((*secret).0, (*interpolated).0);
+            codex32_string_free(secret);
+            codex32_string_free(interpolated);
+
+            for share in &shares {
+                codex32_string_free(*share);
+            }
+        }
+    }
+
+    #[test]
+    fn null_pointers() {
+        unsafe {
+            let mut out = ptr::null_mut();
+            assert_eq!(codex32_validate(ptr::null()), Codex32Error::NullPointer);
+            assert_eq!(
+                codex32_parse(ptr::null(), &mut out),
+                Codex32Error::NullPointer
+            );
+            assert_eq!(codex32_string_len(ptr::null()), 0);
+            codex32_string_free(ptr::null_mut());
+
+            let s = CString::new("ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw").unwrap();
+            assert_eq!(
+                codex32_parse(s.as_ptr(), ptr::null_mut()),
+                Codex32Error::NullPointer
+            );
+            assert_eq!(codex32_parse(s.as_ptr(), &mut out), Codex32Error::Ok);
+            codex32_string_free(out);
+        }
+    }
+}
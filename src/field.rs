@@ -17,7 +17,7 @@
 //! Implements GF32 arithmetic, defined and encoded as in BIP-0173 "bech32"
 //!
 
-use std::{
+use core::{
     convert::{TryFrom, TryInto},
     fmt, num, ops, str,
 };
@@ -44,6 +44,9 @@ const LOG_INV: [u8; 31] = [
     28, 17, 11, 22,  5, 10, 20,
 ];
 
+/// The polynomial x^5 + x^3 + 1, modulo which GF32 multiplication is done
+const MODULUS: u16 = 0b10_1001;
+
 /// Mapping from numeric value to bech32 character
 #[rustfmt::skip]
 const CHARS_LOWER: [char; 32] = [
@@ -53,19 +56,6 @@ const CHARS_LOWER: [char; 32] = [
     'c', 'e', '6', 'm', 'u', 'a', '7', 'l', // +24
 ];
 
-/// Mapping from bech32 character (either case) to numeric value
-#[rustfmt::skip]
-const CHARS_INV: [i8; 128] = [
-    -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
-    -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
-    -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
-    15, -1, 10, 17, 21, 20, 26, 30,  7,  5, -1, -1, -1, -1, -1, -1,
-    -1, 29, -1, 24, 13, 25,  9,  8, 23, -1, 18, 22, 31, 27, 19, -1,
-     1,  0,  3, 16, 11, 28, 12, 14,  6,  4,  2, -1, -1, -1, -1, -1,
-    -1, 29, -1, 24, 13, 25,  9,  8, 23, -1, 18, 22, 31, 27, 19, -1,
-     1,  0,  3, 16, 11, 28, 12, 14,  6,  4,  2, -1, -1, -1, -1, -1,
-];
-
 /// Field-related error
 #[derive(Debug)]
 pub enum Error {
@@ -193,19 +183,22 @@ impl Fe {
     pub const _7: Fe = Fe(30);
     pub const L: Fe = Fe(31);
 
-    /// Iterator over all field elements, in alphabetical order
+    /// All field elements, in alphabetical order
     #[rustfmt::skip]
+    pub const ALPHABETICAL: [Fe; 32] = [
+        Fe::A, Fe::C, Fe::D, Fe::E, Fe::F, Fe::G, Fe::H, Fe::J,
+        Fe::K, Fe::L, Fe::M, Fe::N, Fe::P, Fe::Q, Fe::R, Fe::S,
+        Fe::T, Fe::U, Fe::V, Fe::W, Fe::X, Fe::Y, Fe::Z, Fe::_0,
+        Fe::_2, Fe::_3, Fe::_4, Fe::_5, Fe::_6, Fe::_7, Fe::_8, Fe::_9,
+    ];
+
+    /// Iterator over all field elements, in alphabetical order
     pub fn iter_alpha() -> impl Iterator<Item = Fe> {
-        [
-            Fe::A, Fe::C, Fe::D, Fe::E, Fe::F, Fe::G, Fe::H, Fe::J,
-            Fe::K, Fe::L, Fe::M, Fe::N, Fe::P, Fe::Q, Fe::R, Fe::S,
-            Fe::T, Fe::U, Fe::V, Fe::W, Fe::X, Fe::Y, Fe::Z, Fe::_0,
-            Fe::_2, Fe::_3, Fe::_4, Fe::_5, Fe::_6, Fe::_7, Fe::_8, Fe::_9,
-        ].iter().copied()
+        Fe::ALPHABETICAL.iter().copied()
     }
 
     /// Creates a field element from an integer type
-    pub fn from_u8(byte: u8) -> Result<Fe, super::Error> {
+    pub const fn from_u8(byte: u8) -> Result<Fe, super::Error> {
         if byte < 32 {
             Ok(Fe(byte))
         } else {
@@ -213,6 +206,15 @@ impl Fe {
         }
     }
 
+    /// Creates a field element from the low five bits of a byte, ignoring
+    /// the rest.
+    ///
+    /// Unlike [`Fe::from_u8`] this cannot fail, which makes it convenient in
+    /// constant expressions.
+    pub const fn from_low_bits(byte: u8) -> Fe {
+        Fe(byte & 0x1f)
+    }
+
     /// Creates a field element from an integer type
     pub fn from_int<I>(i: I) -> Result<Fe, super::Error>
     where
@@ -224,25 +226,119 @@ impl Fe {
     }
 
     /// Creates a field element from a single bech32 character
+    ///
+    /// Rather than indexing the table by the (possibly secret) character,
+    /// this scans the whole table, so that its memory access pattern does
+    /// not depend on the character.
     pub fn from_char(c: char) -> Result<Fe, super::Error> {
         let byte = i8::try_from(u32::from(c)).map_err(|_| super::Error::InvalidChar(c))?;
-        let byte = byte as u8; // cast guaranteed to be ok since we started with an unsigned value
-        let u5 =
-            u8::try_from(CHARS_INV[usize::from(byte)]).map_err(|_| super::Error::InvalidChar(c))?;
+        // cast guaranteed to be ok since we started with an unsigned value
+        let byte = byte as u8;
+        // Fold upper case into lower case, then look for the result
+        let lower = byte | (0x20 & ct_lt_mask(byte.wrapping_sub(b'A'), 26));
+        let mut u5 = 0;
+        let mut found = 0;
+        for (i, ch) in CHARS_LOWER.iter().enumerate() {
+            // casts ok since the table has 32 entries, all of them ASCII
+            let mask = ct_eq_mask(*ch as u8, lower);
+            u5 |= i as u8 & mask;
+            found |= mask;
+        }
+        if found == 0 {
+            return Err(super::Error::InvalidChar(c));
+        }
         Ok(Fe(u5))
     }
 
     /// Converts the field element to a lowercase bech32 character
-    pub fn to_char(self) -> char {
-        // casting and indexing fine as we have self.0 in [0, 32) as an invariant
-        CHARS_LOWER[self.0 as usize]
+    ///
+    /// Like [`Fe::from_char`], this scans the whole table rather than
+    /// indexing it by the value of the field element.
+    pub const fn to_char(self) -> char {
+        let mut ret = 0;
+        let mut i = 0;
+        while i < CHARS_LOWER.len() {
+            // cast ok since every bech32 character is ASCII
+            ret |= CHARS_LOWER[i] as u8 & ct_eq_mask(i as u8, self.0);
+            i += 1;
+        }
+        ret as char
     }
 
     /// Converts the field element to a 5-bit u8, with bits representing the coefficients
     /// of the polynomial representation.
-    pub fn to_u8(self) -> u8 {
+    pub const fn to_u8(self) -> u8 {
         self.0
     }
+
+    /// Adds two field elements.
+    ///
+    /// This is the same as the `Add` implementation, which is already
+    /// constant-time, but can be used in constant expressions.
+    pub const fn ct_add(self, other: Fe) -> Fe {
+        Fe(self.0 ^ other.0)
+    }
+
+    /// Multiplies two field elements in constant time.
+    ///
+    /// The `Mul` implementation branches on zero and indexes the log tables
+    /// by the values of its inputs, which may leak them through timing or
+    /// cache side channels. This function instead does a carry-less
+    /// multiplication of the polynomial representations, with every branch
+    /// replaced by masking, and should be used whenever either input is
+    /// secret. It can also be used in constant expressions.
+    pub const fn ct_mul(self, other: Fe) -> Fe {
+        let a = self.0 as u16;
+        let b = other.0 as u16;
+        let mut acc = 0u16;
+        let mut i = 0;
+        while i < 5 {
+            let mask = 0u16.wrapping_sub((b >> i) & 1);
+            acc ^= (a << i) & mask;
+            i += 1;
+        }
+        // Reduce the degree-8 product, one high bit at a time
+        let mut i = 8;
+        while i >= 5 {
+            let mask = 0u16.wrapping_sub((acc >> i) & 1);
+            acc ^= (MODULUS << (i - 5)) & mask;
+            i -= 1;
+        }
+        Fe(acc as u8) // cast ok since we reduced to 5 bits
+    }
+
+    /// Computes the multiplicative inverse of a field element in constant time.
+    ///
+    /// Since every nonzero element x satisfies x^31 = 1, the inverse is x^30,
+    /// which is computed with a fixed sequence of multiplications. Unlike the
+    /// `Div` implementation, this does not panic on zero, but returns zero.
+    pub const fn ct_inv(self) -> Fe {
+        let x2 = self.ct_mul(self);
+        let x4 = x2.ct_mul(x2);
+        let x8 = x4.ct_mul(x4);
+        let x16 = x8.ct_mul(x8);
+        x2.ct_mul(x4).ct_mul(x8).ct_mul(x16)
+    }
+}
+
+/// Returns `0xff` if `a` is less than `b`, and `0` otherwise, without
+/// branching on either
+const fn ct_lt_mask(a: u8, b: u8) -> u8 {
+    // a - b only borrows into the high byte when a is less than b
+    ((a as u16).wrapping_sub(b as u16) >> 8) as u8
+}
+
+/// Returns `0xff` if `a` and `b` are equal, and `0` otherwise, without
+/// branching on either
+const fn ct_eq_mask(a: u8, b: u8) -> u8 {
+    ct_lt_mask(a ^ b, 1)
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Fe {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
 }
 
 impl fmt::Display for Fe {
@@ -266,6 +362,48 @@ impl str::FromStr for Fe {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::string::String;
+    use proptest::prelude::*;
+
+    /// Any field element
+    fn any_fe() -> impl Strategy<Value = Fe> {
+        (0u8..32).prop_map(Fe)
+    }
+
+    /// Any nonzero field element
+    fn nonzero_fe() -> impl Strategy<Value = Fe> {
+        (1u8..32).prop_map(Fe)
+    }
+
+    proptest! {
+        #[test]
+        fn field_axioms(a in any_fe(), b in any_fe(), c in any_fe()) {
+            // Associativity
+            prop_assert_eq!((a + b) + c, a + (b + c));
+            prop_assert_eq!((a * b) * c, a * (b * c));
+            // Commutativity
+            prop_assert_eq!(a + b, b + a);
+            prop_assert_eq!(a * b, b * a);
+            // Distributivity
+            prop_assert_eq!(a * (b + c), a * b + a * c);
+            // Identities
+            prop_assert_eq!(a + Fe::Q, a);
+            prop_assert_eq!(a * Fe::P, a);
+            prop_assert_eq!(a * Fe::Q, Fe::Q);
+            // Additive inverses, which in characteristic 2 are the elements
+            // themselves
+            prop_assert_eq!(a + a, Fe::Q);
+            prop_assert_eq!(a - b + b, a);
+        }
+
+        #[test]
+        fn multiplicative_inverses(a in nonzero_fe(), b in any_fe()) {
+            prop_assert_eq!(a * (Fe::P / a), Fe::P);
+            prop_assert_eq!(a.ct_mul(a.ct_inv()), Fe::P);
+            prop_assert_eq!(b / a * a, b);
+            prop_assert_ne!(a * a, Fe::Q);
+        }
+    }
 
     #[test]
     fn numeric_string() {
@@ -273,6 +411,18 @@ mod tests {
         assert_eq!(s, "qpzry9x8gf2tvdw0s3jn54khce6mua7l");
     }
 
+    #[test]
+    fn char_round_trip() {
+        for byte in 0..=255u8 {
+            let c = char::from(byte);
+            match Fe::from_char(c) {
+                Ok(fe) => assert_eq!(fe.to_char(), c.to_ascii_lowercase()),
+                Err(_) => assert!(CHARS_LOWER.iter().all(|ch| !ch.eq_ignore_ascii_case(&c))),
+            }
+        }
+        assert!(Fe::from_char('\u{2603}').is_err());
+    }
+
     #[test]
     fn translation_wheel() {
         // 1. Produce the translation wheel by multiplying
@@ -317,4 +467,34 @@ mod tests {
         // To verify, start with 3 and move clockwise on the Recovery Wheel
         assert_eq!(s, "36xp78tgk9ldaecjy4mvh0funwr2zq5");
     }
+
+    #[test]
+    fn constant_time_arithmetic() {
+        for a in 0..32 {
+            for b in 0..32 {
+                assert_eq!(Fe(a).ct_mul(Fe(b)), Fe(a) * Fe(b));
+            }
+        }
+
+        assert_eq!(Fe::Q.ct_inv(), Fe::Q);
+        for a in 1..32 {
+            assert_eq!(Fe(a).ct_inv(), Fe::P / Fe(a));
+            assert_eq!(Fe(a).ct_mul(Fe(a).ct_inv()), Fe::P);
+        }
+    }
+
+    #[test]
+    fn const_arithmetic() {
+        // Evaluated at compile time
+        const PRODUCT: Fe = Fe::_5.ct_mul(Fe::_2);
+        const INVERSE: Fe = Fe::_5.ct_inv();
+        const SUM: Fe = Fe::_5.ct_add(Fe::_2);
+        assert_eq!(PRODUCT, Fe::_5 * Fe::_2);
+        assert_eq!(INVERSE, Fe::P / Fe::_5);
+        assert_eq!(SUM, Fe::_5 + Fe::_2);
+
+        for fe in Fe::iter_alpha() {
+            assert_eq!(Fe::from_low_bits(fe.to_u8() | 0xe0), fe);
+        }
+    }
 }
@@ -0,0 +1,170 @@
+// Rust Codex32 Library and Reference Implementation
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Identifiers
+//!
+//! The four-character identifier which ties together a set of shares.
+//!
+
+use core::{fmt, str};
+
+#[cfg(feature = "rand_core")]
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "secp256k1")]
+use {
+    bitcoin_hashes::{hash160, sha512, Hash, HashEngine, Hmac, HmacEngine},
+    secp256k1::{PublicKey, Secp256k1, SecretKey},
+};
+
+use super::Error;
+use crate::field::Fe;
+
+/// A codex32 identifier, consisting of four bech32 characters
+///
+/// Identifiers are case-insensitive and stored in lowercase.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub struct Id([u8; 4]);
+
+impl Id {
+    /// Constructs an identifier from four field elements
+    pub fn from_fes(fes: [Fe; 4]) -> Id {
+        let mut ret = [0; 4];
+        for (ch, fe) in ret.iter_mut().zip(fes.iter()) {
+            *ch = fe.to_char() as u8; // cast ok since bech32 characters are ASCII
+        }
+        Id(ret)
+    }
+
+    /// Derives an identifier from a BIP-32 key fingerprint, by encoding its
+    /// first 20 bits as bech32.
+    ///
+    /// Note that BIP-93 recommends that identifiers do not leak any
+    /// information about the seed, which this does, although only 20 bits
+    /// of the (public) fingerprint of the master key.
+    pub fn from_fingerprint(fingerprint: [u8; 4]) -> Id {
+        let bits = u32::from_be_bytes(fingerprint) >> 12;
+        let fe = |shift: u32| Fe::from_u8(((bits >> shift) & 0x1f) as u8).unwrap(); // unwrap ok since < 32
+        Id::from_fes([fe(15), fe(10), fe(5), fe(0)])
+    }
+
+    /// Derives an identifier from the fingerprint of the BIP-32 master key
+    /// of a seed.
+    ///
+    /// See [`Id::from_fingerprint`] for the encoding and caveats.
+    #[cfg(feature = "secp256k1")]
+    pub fn from_seed_fingerprint(seed: &[u8]) -> Result<Id, Error> {
+        let mut engine = HmacEngine::<sha512::Hash>::new(b"Bitcoin seed");
+        engine.input(seed);
+        let master = Hmac::<sha512::Hash>::from_engine(engine);
+        let sk = SecretKey::from_slice(&master[..32]).map_err(|_| Error::InvalidMasterKey)?;
+        let pk = PublicKey::from_secret_key(&Secp256k1::signing_only(), &sk);
+        let hash = hash160::Hash::hash(&pk.serialize());
+        let mut fingerprint = [0; 4];
+        fingerprint.copy_from_slice(&hash[..4]);
+        Ok(Id::from_fingerprint(fingerprint))
+    }
+
+    /// Generates a random identifier.
+    ///
+    /// Each character is drawn uniformly from the bech32 alphabet, which already
+    /// excludes the easily-confused characters `1`, `b`, `i` and `o`.
+    #[cfg(feature = "rand_core")]
+    pub fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Id {
+        let mut bytes = [0; 4];
+        rng.fill_bytes(&mut bytes);
+        let fe = |b: u8| Fe::from_u8(b & 0x1f).unwrap(); // unwrap ok since b & 0x1f < 32
+        Id::from_fes([fe(bytes[0]), fe(bytes[1]), fe(bytes[2]), fe(bytes[3])])
+    }
+
+    /// The identifier, as a lowercase string
+    pub fn as_str(&self) -> &str {
+        // unwrap ok since we only ever store bech32 characters
+        str::from_utf8(&self.0).unwrap()
+    }
+}
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Debug for Id {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Id").field(&self.as_str()).finish()
+    }
+}
+
+impl str::FromStr for Id {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Id, Error> {
+        let n_chars = s.chars().count();
+        if n_chars != 4 {
+            return Err(Error::IdNotLength4(n_chars));
+        }
+        let mut fes = [Fe::Q; 4];
+        for (fe, ch) in fes.iter_mut().zip(s.chars()) {
+            *fe = Fe::from_char(ch)?;
+        }
+        Ok(Id::from_fes(fes))
+    }
+}
+
+impl AsRef<str> for Id {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn parse() {
+        let id: Id = "leet".parse().unwrap();
+        assert_eq!(id.as_str(), "leet");
+        assert_eq!(id.to_string(), "leet");
+        assert_eq!(format!("{:?}", id), "Id(\"leet\")");
+        assert_eq!("LEET".parse::<Id>().unwrap(), id);
+
+        assert!(matches!("lee".parse::<Id>(), Err(Error::IdNotLength4(3))));
+        assert!(matches!("leets".parse::<Id>(), Err(Error::IdNotLength4(5))));
+        assert!(matches!("lit1".parse::<Id>(), Err(Error::InvalidChar('i'))));
+    }
+
+    #[test]
+    fn fingerprint() {
+        // Fingerprint of the master key of BIP-32 test vector 1
+        assert_eq!(
+            Id::from_fingerprint([0x34, 0x42, 0x19, 0x3e]).as_str(),
+            "x3pp"
+        );
+        assert_eq!(Id::from_fingerprint([0; 4]).as_str(), "qqqq");
+        assert_eq!(Id::from_fingerprint([0xff; 4]).as_str(), "llll");
+    }
+
+    #[test]
+    #[cfg(feature = "secp256k1")]
+    fn seed_fingerprint() {
+        // BIP-32 test vector 1
+        #[rustfmt::skip]
+        let seed = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+            0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+        ];
+        assert_eq!(Id::from_seed_fingerprint(&seed).unwrap().as_str(), "x3pp");
+    }
+}
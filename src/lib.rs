@@ -32,6 +32,11 @@
 //! This library serves as a reference implementation of codex32, and should also
 //! be usable by wallet projects who wish to support the import of codex32 seeds.
 //!
+//! The library is `no_std` and needs only `alloc`. The default `std` feature
+//! is needed for rendering volvelles as SVG and for the command-line tools.
+//! The `ffi` feature provides C bindings, in the [`ffi`] module, and the
+//! `wasm-bindgen` feature JavaScript bindings, in the [`wasm`] module.
+//!
 
 // This is the shittiest lint ever and has literally never been correct when
 // it has fired, and somehow in rust-bitcoin managed NOT to fire in the one
@@ -40,13 +45,47 @@
 #![allow(clippy::suspicious_arithmetic_impl)]
 // This lint also has negative value.
 #![allow(clippy::assertions_on_constants)]
+#![no_std]
+
+#[cfg(feature = "std")]
+extern crate std;
 
+#[macro_use]
+extern crate alloc;
+
+mod buf;
 mod checksum;
+mod correct;
+pub mod dice;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 mod field;
+mod id;
+#[cfg(feature = "rand_core")]
+mod random;
+mod recovery;
+mod refresh;
+mod reshare;
+mod split;
+mod verify;
+pub mod volvelle;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+pub mod worksheet;
 
-pub use checksum::Engine as ChecksumEngine;
+pub use buf::{Codex32Buf, MAX_LEN};
+pub use checksum::{
+    generator_table, Engine as ChecksumEngine, LONG_GENERATOR_TABLE, MAX_RESIDUE_LEN,
+    SHORT_GENERATOR_TABLE,
+};
 pub use field::Fe;
-use std::{cmp, fmt};
+pub use id::Id;
+pub use recovery::Recoverer;
+
+use alloc::{string::String, vec::Vec};
+use core::{cmp, fmt, ops};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
 
 #[derive(Debug)]
 pub enum Error {
@@ -59,6 +98,9 @@ pub enum Error {
     IncompleteGroup(usize),
     /// Tried a codex32 string of an illegal length
     InvalidLength(usize),
+    /// A codex32 string did not contain the separator `1` between its HRP
+    /// and data part
+    MissingSeparator,
     /// Tried to decode a character which was not part of the bech32 alphabet,
     /// or, if in the HRP, was not ASCII.
     InvalidChar(char),
@@ -68,7 +110,8 @@ pub enum Error {
     InvalidChecksum {
         /// Checksum we used, "long" or "short"
         checksum: &'static str,
-        /// The string with the bad checksum
+        /// The string with the bad checksum, with everything past the
+        /// header redacted
         string: String,
     },
     /// Threshold was not an allowed value (2 through 9, or 0)
@@ -86,10 +129,33 @@ pub enum Error {
     MismatchedThreshold(usize, usize),
     /// A set of shares to be interpolated did not all have the same ID
     MismatchedId(String, String),
+    /// A set of re-sharing contributions to be combined did not all have
+    /// the same share index
+    MismatchedShareIndex(Fe, Fe),
+    /// Tried to refresh a set of shares without changing the identifier,
+    /// which would allow old and new shares to be mixed
+    UnchangedId(String),
     /// A share index was repeated in the set of shares to interpolate.
     RepeatedIndex(Fe),
+    /// A share was given whose index was not among those declared up front.
+    UnexpectedIndex(Fe),
     /// A set of shares to be interpolated did not have enough shares
     ThresholdNotPassed { threshold: usize, n_shares: usize },
+    /// Tried to split a seed into more shares than there are share indices
+    TooManyShares(usize),
+    /// Tried to generate a seed of a length not allowed by BIP-93 (16 to 64 bytes)
+    InvalidSeedLength(usize),
+    /// A die roll or coin flip was not a valid observation of that die
+    InvalidDieRoll(String),
+    /// Not enough entropy was collected to produce a seed of the requested length
+    NotEnoughEntropy { needed: usize, available: usize },
+    /// A caller-provided buffer was too small for the output
+    BufferTooSmall { needed: usize, available: usize },
+    /// The BIP-32 master key derived from a seed was not a valid secret key
+    InvalidMasterKey,
+    /// A string had an invalid checksum which could not be fixed by
+    /// substituting a single character
+    Uncorrectable,
 }
 
 impl From<field::Error> for Error {
@@ -108,23 +174,58 @@ pub enum Case {
 }
 
 /// A codex32 string, containing a valid checksum
-#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+///
+/// Since the string may encode a secret, it does not implement `Display`,
+/// and its `Debug` output shows only the header. To get at the full string,
+/// use [`Codex32String::expose_secret`], or [`Codex32String::display_secret`]
+/// to format it.
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Codex32String(String);
 
-impl fmt::Display for Codex32String {
+#[cfg(feature = "zeroize")]
+impl Drop for Codex32String {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for Codex32String {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Display::fmt(&self.0, f)
+        let parts = self.parts();
+        f.debug_struct("Codex32String")
+            .field("hrp", &parts.hrp)
+            .field("threshold", &parts.threshold)
+            .field("id", &parts.id)
+            .field("share_index", &parts.share_index)
+            .field("payload", &"<redacted>")
+            .finish()
+    }
+}
+
+/// The full string of a [`Codex32String`], returned by
+/// [`Codex32String::display_secret`]
+struct DisplaySecret<'s>(&'s Codex32String);
+
+impl<'s> fmt::Display for DisplaySecret<'s> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&(self.0).0, f)
     }
 }
 
 impl Codex32String {
+    /// The full string, including the (possibly secret) payload
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+
+    /// Opts in to displaying the full string, including the (possibly
+    /// secret) payload, e.g. with `format!("{}", s.display_secret())`
+    pub fn display_secret(&self) -> impl fmt::Display + '_ {
+        DisplaySecret(self)
+    }
+
     fn sanity_check(&self) -> Result<(), Error> {
-        let parts = self.parts_inner()?;
-        let incomplete_group = (parts.payload.len() * 5) % 8;
-        if incomplete_group > 4 {
-            return Err(Error::IncompleteGroup(incomplete_group));
-        }
-        Ok(())
+        sanity_check(&self.0)
     }
 
     /// Construct a codex32 string from a not-yet-checksummed string
@@ -135,105 +236,61 @@ impl Codex32String {
         } else {
             (15, checksum::Engine::new_codex32_long())
         };
-        s.reserve_exact(len);
+        // Copy the string into one with room for the checksum, rather than
+        // growing it, which could leave an unwiped copy of it on the heap. The
+        // copy is wiped on drop, including on any of the error paths below.
+        let mut ret = Codex32String(String::with_capacity(s.len() + len));
+        ret.0.push_str(&s);
+        #[cfg(feature = "zeroize")]
+        s.zeroize();
+        #[cfg(not(feature = "zeroize"))]
+        s.clear();
 
         // Split out the HRP
-        let mut rsplit = s.rsplitn(2, '1');
+        let mut rsplit = ret.0.rsplitn(2, '1');
         let (hrp, real_string) = match (rsplit.next(), rsplit.next()) {
             (Some(s2), Some(s1)) => (s1, s2),
-            _ => ("", &s[..]),
+            _ => return Err(Error::MissingSeparator),
         };
         // Compute the checksum
         checksum.input_hrp(hrp)?;
         checksum.input_data_str(real_string)?;
         checksum.input_own_target();
-        let case = checksum.get_case().unwrap();
+        // A string with no letters has no case, so use lowercase
+        let case = checksum.get_case().unwrap_or(Case::Lower);
         for ch in checksum.into_residue() {
             match case {
-                Case::Lower => s.push(ch.to_char()),
-                Case::Upper => s.push(ch.to_char().to_uppercase().next().unwrap()),
+                Case::Lower => ret.0.push(ch.to_char()),
+                Case::Upper => ret.0.push(ch.to_char().to_uppercase().next().unwrap()),
             }
         }
 
-        let ret = Codex32String(s);
         ret.sanity_check()?;
         Ok(ret)
     }
 
     /// Construct a codex32 string from an already-checksummed string
     pub fn from_string(s: String) -> Result<Self, Error> {
-        let (name, mut checksum) = if s.len() >= 48 && s.len() < 94 {
-            ("short", checksum::Engine::new_codex32_short())
-        } else if s.len() >= 125 && s.len() < 128 {
-            ("long", checksum::Engine::new_codex32_long())
-        } else {
-            return Err(Error::InvalidLength(s.len()));
-        };
-
-        // Split out the HRP
-        let mut rsplit = s.rsplitn(2, '1');
-        let (hrp, real_string) = match (rsplit.next(), rsplit.next()) {
-            (Some(s2), Some(s1)) => (s1, s2),
-            _ => ("", &s[..]),
-        };
-        checksum.input_hrp(hrp)?;
-        checksum.input_data_str(real_string)?;
-        if !checksum.is_valid() {
-            return Err(Error::InvalidChecksum {
-                checksum: name,
-                string: s,
-            });
-        }
-        // Looks good, return
+        // Wrap the string first, so that it is wiped on drop if it is invalid
         let ret = Codex32String(s);
-        ret.sanity_check()?;
+        check_string(&ret.0)?;
         Ok(ret)
     }
 
     /// Break the string up into its constituent parts
-    fn parts_inner(&self) -> Result<Parts, Error> {
-        let mut rsplit = self.0.rsplitn(2, '1');
-        let (hrp, s) = match (rsplit.next(), rsplit.next()) {
-            (Some(s2), Some(s1)) => (s1, s2),
-            _ => ("", &self.0[..]),
-        };
-        let checksum_len = if self.0.len() > 93 { 15 } else { 13 };
-        let ret = Parts {
-            hrp,
-            threshold: match s.as_bytes()[0] {
-                b'0' => 0,
-                b'2' => 2,
-                b'3' => 3,
-                b'4' => 4,
-                b'5' => 5,
-                b'6' => 6,
-                b'7' => 7,
-                b'8' => 8,
-                b'9' => 9,
-                _ => return Err(Error::InvalidThreshold(s.as_bytes()[0].into())),
-            },
-            id: &s[1..5],
-            share_index: Fe::from_char(s.as_bytes()[5].into()).unwrap(),
-            payload: &s[6..s.len() - checksum_len],
-            checksum: &s[s.len() - checksum_len..],
-        };
-        if ret.threshold == 0 && ret.share_index != Fe::S {
-            return Err(Error::InvalidShareIndex(ret.share_index));
-        }
-        Ok(ret)
+    fn parts_inner(&self) -> Result<Parts<'_>, Error> {
+        Parts::parse(&self.0)
     }
 
     /// Break the string up into its constituent parts
-    pub fn parts(&self) -> Parts {
+    pub fn parts(&self) -> Parts<'_> {
         // unwrap OK since we validated the input on parse
         self.parts_inner().unwrap()
     }
 
-    /// Interpolate a set of shares to derive a share at a specific index.
-    ///
-    /// Using the index `Fe::S` will recover the master seed.
-    pub fn interpolate_at(shares: &[Codex32String], target: Fe) -> Result<Codex32String, Error> {
-        // Collect indices and sanity check
+    /// Checks that a set of shares are compatible with each other and that
+    /// there are enough of them to interpolate, and returns their indices.
+    fn interpolation_indices(shares: &[Codex32String]) -> Result<Vec<Fe>, Error> {
         if shares.is_empty() {
             return Err(Error::ThresholdNotPassed {
                 threshold: 1,
@@ -250,80 +307,68 @@ impl Codex32String {
         }
         for share in shares {
             let parts = share.parts();
-            if shares[0].0.len() != share.0.len() {
-                return Err(Error::MismatchedLength(shares[0].0.len(), share.0.len()));
-            }
-            if s0_parts.hrp != parts.hrp {
-                return Err(Error::MismatchedHrp(s0_parts.hrp.into(), parts.hrp.into()));
-            }
-            if s0_parts.threshold != parts.threshold {
-                return Err(Error::MismatchedThreshold(
-                    s0_parts.threshold,
-                    parts.threshold,
-                ));
-            }
-            if s0_parts.id != parts.id {
-                return Err(Error::MismatchedId(s0_parts.id.into(), parts.id.into()));
-            }
+            check_compatible(&shares[0].0, &s0_parts, &share.0, &parts)?;
             indices.push(parts.share_index);
         }
+        Ok(indices)
+    }
 
-        // Do lagrange interpolation
-        let mut mult = Fe::P;
+    /// The data part of the string (everything after the HRP and separator),
+    /// as field elements
+    fn data_fes(&self) -> impl Iterator<Item = Fe> + '_ {
+        let hrp_len = self.parts().hrp.len();
+        self.0[hrp_len + 1..]
+            .chars()
+            .map(|c| Fe::from_char(c).unwrap()) // unwrap ok since string is valid bech32
+    }
+
+    /// Assembles a string from an HRP and a data part, matching the case of
+    /// the data part to that of the HRP
+    fn from_hrp_and_data(hrp: &str, mut data: Vec<Fe>) -> Codex32String {
+        let mut s = String::with_capacity(hrp.len() + 1 + data.len());
+        s.push_str(hrp);
+        s.push('1');
+        let upper = is_upper(hrp);
+        s.extend(data.iter().map(|fe| to_char(*fe, upper)));
+        #[cfg(feature = "zeroize")]
+        data.zeroize();
+        #[cfg(not(feature = "zeroize"))]
+        data.clear();
+        Codex32String(s)
+    }
+
+    /// Interpolate a set of shares to derive a share at a specific index.
+    ///
+    /// Using the index `Fe::S` will recover the master seed.
+    pub fn interpolate_at(shares: &[Codex32String], target: Fe) -> Result<Codex32String, Error> {
+        // Collect indices and sanity check
+        let indices = Codex32String::interpolation_indices(shares)?;
         for i in 0..shares.len() {
             if indices[i] == target {
                 // If we're trying to output an input share, just output it directly.
-                // Naive Lagrange multiplication would otherwise multiply by 0.
                 return Ok(shares[i].clone());
             }
-
-            mult *= indices[i] + target;
         }
 
-        let payload_len = 6 + s0_parts.payload.len() + s0_parts.checksum.len();
-        let hrp_len = shares[0].0.len() - payload_len;
-        let mut result = vec![Fe::Q; payload_len];
-
-        for i in 0..shares.len() {
-            let mut inv = Fe::P;
-            for j in 0..shares.len() {
-                inv *= indices[j]
-                    + if i == j {
-                        target
-                    } else {
-                        // If there is a repeated index, just call this an error. Technically
-                        // speaking, we could reject the other one and re-do the threshold
-                        // check in case we had enough unique ones .. but easier to just make
-                        // it the user's responsibility to provide unique indices to begin with.
-                        if indices[i] == indices[j] {
-                            return Err(Error::RepeatedIndex(indices[i]));
-                        }
-                        indices[i]
-                    }
-            }
-
-            for (j, res_j) in result.iter_mut().enumerate() {
-                let ch_at_i = char::from(shares[i].0.as_bytes()[hrp_len + j]);
-                *res_j += mult / inv * Fe::from_char(ch_at_i).unwrap();
+        // Do lagrange interpolation
+        let coeffs = lagrange_coefficients(&indices, target)?;
+        let mut result = vec![Fe::Q; shares[0].0.len() - shares[0].parts().hrp.len() - 1];
+        for (share, coeff) in shares.iter().zip(coeffs) {
+            for (res_j, fe) in result.iter_mut().zip(share.data_fes()) {
+                *res_j += coeff.ct_mul(fe);
             }
         }
 
-        let mut s = s0_parts.hrp.to_owned();
-        s.push('1');
-        if s0_parts.hrp.chars().all(char::is_uppercase) {
-            s.extend(
-                result
-                    .into_iter()
-                    .map(Fe::to_char)
-                    .map(|c| c.to_ascii_uppercase()),
-            );
-        } else {
-            s.extend(result.into_iter().map(Fe::to_char));
-        }
-        Ok(Codex32String(s))
+        Ok(Codex32String::from_hrp_and_data(
+            shares[0].parts().hrp,
+            result,
+        ))
     }
 
     /// Creates a S share from bare seed data
+    ///
+    /// The case of the output matches that of the HRP, which must not be
+    /// mixed-case.
     pub fn from_seed(
         hrp: &str,
         threshold: usize,
@@ -331,13 +376,7 @@ impl Codex32String {
         share_idx: Fe,
         data: &[u8],
     ) -> Result<Codex32String, Error> {
-        if id.len() != 4 {
-            return Err(Error::IdNotLength4(id.len()));
-        }
-
-        let mut ret = String::with_capacity(hrp.len() + 6 + (data.len() * 8 + 4) / 5);
-        ret.push_str(hrp);
-        ret.push('1');
+        let id: Id = id.parse()?;
         let k = match threshold {
             0 => Fe::_0,
             2 => Fe::_2,
@@ -350,10 +389,23 @@ impl Codex32String {
             9 => Fe::_9,
             x => return Err(Error::InvalidThresholdN(x)),
         };
-        // FIXME correct case to match HRP
-        ret.push(k.to_char());
-        ret.push_str(id);
-        ret.push(share_idx.to_char());
+        let upper = is_upper(hrp);
+        let case = |fe: Fe| to_char(fe, upper);
+
+        // Reserve enough room for the longest checksum up front, so that the
+        // payload is never copied by a reallocation. The string is wiped on
+        // drop, including on any of the error paths below.
+        let mut ret = Codex32String(String::with_capacity(
+            hrp.len() + 1 + 6 + (data.len() * 8 + 4) / 5 + 15,
+        ));
+        let buf = &mut ret.0;
+        buf.push_str(hrp);
+        buf.push('1');
+        buf.push(case(k));
+        for ch in id.as_str().chars() {
+            buf.push(if upper { ch.to_ascii_uppercase() } else { ch });
+        }
+        buf.push(case(share_idx));
 
         // Convert byte data to base 32
         let mut next_u5 = 0;
@@ -361,42 +413,197 @@ impl Codex32String {
         for byte in data {
             // Each byte provides at least one u5. Push that.
             let u5 = (next_u5 << (5 - rem)) | byte >> (3 + rem);
-            ret.push(Fe::from_u8(u5).unwrap().to_char());
+            buf.push(case(Fe::from_u8(u5).unwrap()));
             next_u5 = byte & ((1 << (3 + rem)) - 1);
             // If there were 2 or more bits from the last iteration, then
             // this iteration will push *two* u5s.
             if rem >= 2 {
-                ret.push(Fe::from_u8(next_u5 >> (rem - 2)).unwrap().to_char());
+                buf.push(case(Fe::from_u8(next_u5 >> (rem - 2)).unwrap()));
                 next_u5 &= (1 << (rem - 2)) - 1;
             }
             rem = (rem + 8) % 5;
         }
         if rem > 0 {
-            ret.push(Fe::from_u8(next_u5 << (5 - rem)).unwrap().to_char());
+            buf.push(case(Fe::from_u8(next_u5 << (5 - rem)).unwrap()));
         }
 
         // Initialize checksum engine with HRP and header
-        let mut checksum = if data.len() < 51 {
+        // Choose the checksum by the length of the string, as the parser does,
+        // rather than that of the seed, since the HRP is of variable length
+        let mut checksum = if buf.len() < 81 {
             checksum::Engine::new_codex32_short()
         } else {
             checksum::Engine::new_codex32_long()
         };
         checksum.input_hrp(hrp)?;
-        checksum.input_data_str(&ret[hrp.len() + 1..])?;
+        checksum.input_data_str(&buf[hrp.len() + 1..])?;
         // Now, to compute the checksum, we stick the target residue onto the end
         // of the input string, the take the resulting residue as the checksum
         checksum.input_own_target();
-        ret.extend(checksum.into_residue().into_iter().map(Fe::to_char));
+        buf.extend(checksum.into_residue().into_iter().map(case));
 
-        let mut checksum = checksum::Engine::new_codex32_short();
-        checksum.input_hrp(hrp)?;
-        checksum.input_data_str(&ret[hrp.len() + 1..])?;
-        Ok(Codex32String(ret))
+        // Not every seed length gives a string of a length allowed by BIP-93
+        check_string(&ret.0)?;
+        Ok(ret)
+    }
+}
+
+/// Checks that a string is a valid, checksummed, codex32 string
+///
+/// Does not allocate, except to construct an [`Error::InvalidChecksum`].
+fn check_string(s: &str) -> Result<(), Error> {
+    let (name, mut checksum) = if s.len() >= 48 && s.len() < 94 {
+        ("short", checksum::Engine::new_codex32_short())
+    } else if s.len() >= 125 && s.len() < 128 {
+        ("long", checksum::Engine::new_codex32_long())
+    } else {
+        return Err(Error::InvalidLength(s.len()));
+    };
+
+    // Split out the HRP
+    let mut rsplit = s.rsplitn(2, '1');
+    let (hrp, real_string) = match (rsplit.next(), rsplit.next()) {
+        (Some(s2), Some(s1)) => (s1, s2),
+        _ => return Err(Error::MissingSeparator),
+    };
+    checksum.input_hrp(hrp)?;
+    checksum.input_data_str(real_string)?;
+    if !checksum.is_valid() {
+        return Err(Error::InvalidChecksum {
+            checksum: name,
+            string: redact(s),
+        });
+    }
+    // Looks good
+    sanity_check(s)
+}
+
+/// Whether strings with the given HRP are written in upper case
+///
+/// This follows the case check of the checksum engine, under which digits
+/// have no case and every other character which is not a lower-case letter,
+/// such as punctuation, counts as upper case. Since an HRP may not be
+/// mixed-case, one such character means that they all are.
+fn is_upper(hrp: &str) -> bool {
+    hrp.chars()
+        .any(|c| !c.is_numeric() && !c.is_ascii_lowercase())
+}
+
+/// Converts a field element to a bech32 character of the given case
+fn to_char(fe: Fe, upper: bool) -> char {
+    if upper {
+        fe.to_char().to_ascii_uppercase()
+    } else {
+        fe.to_char()
+    }
+}
+
+/// Checks that the header of a string is valid and that its payload does
+/// not end in an entirely extraneous character
+fn sanity_check(s: &str) -> Result<(), Error> {
+    let parts = Parts::parse(s)?;
+    let incomplete_group = (parts.payload.len() * 5) % 8;
+    if incomplete_group > 4 {
+        return Err(Error::IncompleteGroup(incomplete_group));
+    }
+    Ok(())
+}
+
+/// Checks that two shares have the same length, HRP, threshold and
+/// identifier, so that they can be interpolated together
+fn check_compatible(s1: &str, parts1: &Parts, s2: &str, parts2: &Parts) -> Result<(), Error> {
+    if s1.len() != s2.len() {
+        return Err(Error::MismatchedLength(s1.len(), s2.len()));
+    }
+    if parts1.hrp != parts2.hrp {
+        return Err(Error::MismatchedHrp(parts1.hrp.into(), parts2.hrp.into()));
+    }
+    if parts1.threshold != parts2.threshold {
+        return Err(Error::MismatchedThreshold(
+            parts1.threshold,
+            parts2.threshold,
+        ));
+    }
+    if parts1.id != parts2.id {
+        return Err(Error::MismatchedId(parts1.id.into(), parts2.id.into()));
+    }
+    Ok(())
+}
+
+/// Checks that a set of indices are non-empty and distinct, and returns the
+/// position of `target` among them, if any
+fn check_indices(indices: &[Fe], target: Fe) -> Result<Option<usize>, Error> {
+    if indices.is_empty() {
+        return Err(Error::ThresholdNotPassed {
+            threshold: 1,
+            n_shares: 0,
+        });
+    }
+    // If there is a repeated index, just call this an error. Technically
+    // speaking, we could reject the other one and re-do the threshold
+    // check in case we had enough unique ones .. but easier to just make
+    // it the user's responsibility to provide unique indices to begin with.
+    for i in 0..indices.len() {
+        if indices[i + 1..].contains(&indices[i]) {
+            return Err(Error::RepeatedIndex(indices[i]));
+        }
+    }
+    Ok(indices.iter().position(|idx| *idx == target))
+}
+
+/// Computes the Lagrange coefficient of the `i`th of a set of indices, for
+/// interpolating at `target`.
+///
+/// The indices must be distinct, and must not include `target`.
+fn lagrange_coefficient(indices: &[Fe], i: usize, target: Fe) -> Fe {
+    let mut mult = Fe::P;
+    let mut inv = Fe::P;
+    for (j, idx) in indices.iter().enumerate() {
+        mult *= *idx + target;
+        inv *= *idx + if i == j { target } else { indices[i] };
     }
+    mult / inv
+}
+
+/// Computes the Lagrange coefficients for interpolating a set of shares.
+///
+/// Given the indices of a set of shares, returns one coefficient per share,
+/// such that multiplying every character of each share by its coefficient,
+/// and summing the results, gives the share at `target`. Using the index
+/// `Fe::S` gives the coefficients for recovering the master seed.
+///
+/// This lets the shares be combined one at a time, without ever holding
+/// them all in memory together. If `target` is itself one of the indices,
+/// its coefficient is 1 and all others are 0.
+///
+/// Errors if `indices` is empty or if any index is repeated.
+pub fn lagrange_coefficients(indices: &[Fe], target: Fe) -> Result<Vec<Fe>, Error> {
+    if let Some(i) = check_indices(indices, target)? {
+        // Naive Lagrange multiplication would multiply everything by 0.
+        let mut ret = vec![Fe::Q; indices.len()];
+        ret[i] = Fe::P;
+        return Ok(ret);
+    }
+    Ok((0..indices.len())
+        .map(|i| lagrange_coefficient(indices, i, target))
+        .collect())
+}
+
+/// Replaces everything in a would-be codex32 string past the header (the
+/// HRP, separator, threshold, identifier and share index) with `*`s
+fn redact(s: &str) -> String {
+    let header_len = match s.rfind('1') {
+        Some(sep) => sep + 7,
+        None => 6,
+    };
+    s.chars()
+        .enumerate()
+        .map(|(i, c)| if i < header_len { c } else { '*' })
+        .collect()
 }
 
 /// A codex32 string, split into its constituent partrs
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Parts<'s> {
     hrp: &'s str,
     threshold: usize,
@@ -406,14 +613,121 @@ pub struct Parts<'s> {
     checksum: &'s str,
 }
 
+impl<'s> fmt::Debug for Parts<'s> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Parts")
+            .field("hrp", &self.hrp)
+            .field("threshold", &self.threshold)
+            .field("id", &self.id)
+            .field("share_index", &self.share_index)
+            .field("payload", &"<redacted>")
+            .field("checksum", &"<redacted>")
+            .finish()
+    }
+}
+
 impl<'s> Parts<'s> {
+    /// Breaks a string up into its constituent parts, checking the header
+    /// but not the checksum
+    fn parse(s: &'s str) -> Result<Parts<'s>, Error> {
+        let mut rsplit = s.rsplitn(2, '1');
+        let (hrp, data) = match (rsplit.next(), rsplit.next()) {
+            (Some(s2), Some(s1)) => (s1, s2),
+            _ => return Err(Error::MissingSeparator),
+        };
+        let checksum_len = if s.len() > 93 { 15 } else { 13 };
+        // The data part must hold at least a header and a checksum, which a
+        // long HRP or a short unchecksummed string may not leave room for.
+        if data.len() < 6 + checksum_len {
+            return Err(Error::InvalidLength(s.len()));
+        }
+        let ret = Parts {
+            hrp,
+            threshold: match data.as_bytes()[0] {
+                b'0' => 0,
+                b'2' => 2,
+                b'3' => 3,
+                b'4' => 4,
+                b'5' => 5,
+                b'6' => 6,
+                b'7' => 7,
+                b'8' => 8,
+                b'9' => 9,
+                _ => return Err(Error::InvalidThreshold(data.as_bytes()[0].into())),
+            },
+            id: &data[1..5],
+            share_index: Fe::from_char(data.as_bytes()[5].into()).unwrap(),
+            payload: &data[6..data.len() - checksum_len],
+            checksum: &data[data.len() - checksum_len..],
+        };
+        if ret.threshold == 0 && ret.share_index != Fe::S {
+            return Err(Error::InvalidShareIndex(ret.share_index));
+        }
+        Ok(ret)
+    }
+
+    /// The human-readable part
+    pub fn hrp(&self) -> &'s str {
+        self.hrp
+    }
+
+    /// The threshold, with 0 meaning the string is an unshared secret
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// The four-character identifier
+    pub fn id(&self) -> &'s str {
+        self.id
+    }
+
+    /// The share index
+    pub fn share_index(&self) -> Fe {
+        self.share_index
+    }
+
+    /// The checksum characters
+    pub fn checksum(&self) -> &'s str {
+        self.checksum
+    }
+
     /// Extract the binary data from a checksummed string
     ///
     /// If the string does not have a multiple-of-8 number of bits, right-pad the
     /// final byte with 0s.
-    pub fn data(&self) -> Vec<u8> {
-        let mut ret = Vec::with_capacity((self.payload.len() * 5 + 7) / 8);
+    pub fn data(&self) -> SecretSeed {
+        let mut ret = Vec::with_capacity(self.data_len());
+        self.decode_data(|byte| ret.push(byte));
+        SecretSeed(ret)
+    }
 
+    /// The length of the binary data, in bytes
+    pub fn data_len(&self) -> usize {
+        self.payload.len() * 5 / 8
+    }
+
+    /// Extract the binary data from a checksummed string into a buffer,
+    /// without allocating, returning the number of bytes written.
+    ///
+    /// Errors if the buffer is shorter than [`Parts::data_len`].
+    pub fn data_into(&self, out: &mut [u8]) -> Result<usize, Error> {
+        let len = self.data_len();
+        if out.len() < len {
+            return Err(Error::BufferTooSmall {
+                needed: len,
+                available: out.len(),
+            });
+        }
+        let mut pos = 0;
+        self.decode_data(|byte| {
+            out[pos] = byte;
+            pos += 1;
+        });
+        Ok(len)
+    }
+
+    /// Converts the payload to bytes, passing each one to `push`
+    fn decode_data<F: FnMut(u8)>(&self, mut push: F) {
         let mut next_byte = 0;
         let mut rem = 0;
         for ch in self.payload.chars() {
@@ -425,27 +739,89 @@ impl<'s> Parts<'s> {
                 }
                 cmp::Ordering::Equal => {
                     // If we are exactly 3 bits from the start then this char fills in the byte
-                    ret.push(next_byte | fe.to_u8());
+                    push(next_byte | fe.to_u8());
                     next_byte = 0;
                 }
                 cmp::Ordering::Greater => {
                     // Otherwise we have to break it in two
                     let overshoot = rem - 3;
                     assert!(overshoot > 0);
-                    ret.push(next_byte | (fe.to_u8() >> overshoot));
+                    push(next_byte | (fe.to_u8() >> overshoot));
                     next_byte = fe.to_u8() << (8 - overshoot);
                 }
             }
             rem = (rem + 5) % 8;
         }
         debug_assert!(rem <= 4); // checked when parsing the string
-        ret
+    }
+}
+
+/// Binary seed data extracted from a codex32 string
+///
+/// Dereferences to the underlying bytes. Its `Debug` output is redacted so
+/// that seeds do not end up in logs, and if the `zeroize` feature is enabled,
+/// its memory is wiped when it is dropped.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretSeed(Vec<u8>);
+
+impl SecretSeed {
+    /// The seed data, as bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretSeed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretSeed(<{} bytes redacted>)", self.0.len())
+    }
+}
+
+impl ops::Deref for SecretSeed {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for SecretSeed {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for SecretSeed {
+    fn drop(&mut self) {
+        self.0.zeroize();
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::string::ToString;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn generated_strings_parse(
+            hrp in "[a-z]{1,10}",
+            threshold in prop_oneof![Just(0usize), 2usize..=9],
+            share_index in 0u8..32,
+            seed in vec(any::<u8>(), 0..=70),
+        ) {
+            let share_index = Fe::from_u8(share_index).unwrap();
+            // Errors are fine (e.g. bad lengths), but anything we produce
+            // must be accepted by the parser and give back the seed
+            if let Ok(c32) = Codex32String::from_seed(&hrp, threshold, "leet", share_index, &seed) {
+                let parsed = Codex32String::from_string(c32.expose_secret().into()).unwrap();
+                prop_assert_eq!(&parsed, &c32);
+                prop_assert_eq!(&parsed.parts().data()[..], &seed[..]);
+            }
+        }
+    }
 
     // hex-encoding is a niche requirement and does not belong in the standard
     // library of a systems programming language -- rust IRC
@@ -472,6 +848,17 @@ mod tests {
         assert_eq!(hex(&c32_parts.data()), "318c6318c6318c6318c6318c6318c631");
         // Don't check master node xpriv; this is implied by the master seed
         // and would require extra dependencies to compute
+        assert_eq!(
+            format!("{:?}", c32_parts.data()),
+            "SecretSeed(<16 bytes redacted>)"
+        );
+        assert_eq!(
+            format!("{:?}", c32),
+            "Codex32String { hrp: \"ms\", threshold: 0, id: \"test\", share_index: Fe(16), payload: \"<redacted>\" }",
+        );
+        assert!(!format!("{:?}", c32_parts).contains("xxxx"));
+        assert_eq!(c32.expose_secret(), secret);
+        assert_eq!(format!("{}", c32.display_secret()), secret);
     }
 
     #[test]
@@ -485,13 +872,13 @@ mod tests {
 
         let share_d = Codex32String::interpolate_at(&share_ac, Fe::D).unwrap();
         assert_eq!(
-            share_d.to_string(),
+            share_d.expose_secret(),
             "MS12NAMEDLL4F8JLH4E5VDVULDLFXU2JHDNLSM97XVENRXEG"
         );
 
         let seed = Codex32String::interpolate_at(&share_ac, Fe::S).unwrap();
         assert_eq!(
-            seed.to_string(),
+            seed.expose_secret(),
             "MS12NAMES6XQGUZTTXKEQNJSJZV4JV3NZ5K3KWGSPHUH6EVW"
         );
         assert_eq!(
@@ -517,15 +904,15 @@ mod tests {
             Codex32String::interpolate_at(&share_sac, Fe::F).unwrap(),
         ];
         assert_eq!(
-            share_def[0].to_string(),
+            share_def[0].expose_secret(),
             "ms13cashd0wsedstcdcts64cd7wvy4m90lm28w4ffupqs7rm",
         );
         assert_eq!(
-            share_def[1].to_string(),
+            share_def[1].expose_secret(),
             "ms13casheekgpemxzshcrmqhaydlp6yhms3ws7320xyxsar9",
         );
         assert_eq!(
-            share_def[2].to_string(),
+            share_def[2].expose_secret(),
             "ms13cashf8jh6sdrkpyrsp5ut94pj8ktehhw2hfvyrj48704",
         );
     }
@@ -541,12 +928,12 @@ mod tests {
         ];
         let seed = Codex32String::from_seed("ms", 0, "leet", Fe::S, &seed_b).unwrap();
         assert_eq!(
-            seed.to_string(),
+            seed.expose_secret(),
             "ms10leetsllhdmn9m42vcsamx24zrxgs3qrl7ahwvhw4fnzrhve25gvezzyqqtum9pgv99ycma",
         );
         // Our code sticks 0s onto the bitstring to get a multiple of 5 bits. Confirm that
         // other choices would've worked.
-        assert_eq!(seed.parts().data(), seed_b);
+        assert_eq!(&seed.parts().data()[..], &seed_b[..]);
         let alt_encodings = [
             "ms10leetsllhdmn9m42vcsamx24zrxgs3qrl7ahwvhw4fnzrhve25gvezzyqqtum9pgv99ycma",
             "ms10leetsllhdmn9m42vcsamx24zrxgs3qrl7ahwvhw4fnzrhve25gvezzyqpj82dp34u6lqtd",
@@ -567,7 +954,7 @@ mod tests {
         ];
         for alt in &alt_encodings {
             let seed = Codex32String::from_string(alt.to_string()).unwrap();
-            assert_eq!(seed.parts().data(), seed_b);
+            assert_eq!(&seed.parts().data()[..], &seed_b[..]);
         }
     }
 
@@ -603,9 +990,12 @@ mod tests {
             "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx8kp950klmrlsm",
         ];
         for chk in &bad_checksums {
-            if let Err(Error::InvalidChecksum { .. }) = Codex32String::from_string(chk.to_string())
+            if let Err(Error::InvalidChecksum { string, .. }) =
+                Codex32String::from_string(chk.to_string())
             {
-                // ok
+                assert!(string.starts_with("ms10tests"));
+                assert_eq!(string.len(), chk.len());
+                assert!(string[9..].chars().all(|c| c == '*'));
             } else {
                 panic!(
                     "Accepted {} with bad checksum, or raised a different error",
@@ -857,4 +1247,136 @@ mod tests {
             "MS100C8VSM32ZXFGUHPCHTLUPZRY9X8GF2TVDW0S3JN54KHCE6MUA7LQPZYGSFJD6AN074RXVCEMLH8WU3TK925ACDEFGHJKLMNPQRSTUVWXY06FHPV80UNDVARHRAK"
         );
     }
+
+    #[test]
+    fn no_reallocation() {
+        // Growing a string would leave an unwiped copy of its payload behind,
+        // and give it more capacity than was reserved up front
+        let data = [0x31; 64];
+        for len in (16..=44).chain(63..=64) {
+            let c32 = Codex32String::from_seed("ms", 0, "leet", Fe::S, &data[..len]).unwrap();
+            assert_eq!(c32.0.capacity(), 3 + 6 + (len * 8 + 4) / 5 + 15);
+        }
+        for s in [
+            "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx",
+            "MS100C8VSM32ZXFGUHPCHTLUPZRY9X8GF2TVDW0S3JN54KHCE6MUA7LQPZYGSFJD6AN074RXVCEMLH8WU3TK925ACDEFGHJKLMNPQRSTUVWXY06F",
+        ]
+        .iter()
+        {
+            let c32 = Codex32String::from_unchecksummed_string(s.to_string()).unwrap();
+            assert_eq!(c32.0.capacity(), c32.0.len());
+        }
+    }
+
+    #[test]
+    fn malformed_structure() {
+        // Too short to hold a header and checksum, once the checksum is added
+        for s in ["", "ms", "ms1", "ms10", "ms10test", "0", "10"].iter() {
+            assert!(Codex32String::from_unchecksummed_string(s.to_string()).is_err());
+        }
+        let long_hrp = "x".repeat(40) + "10test";
+        assert!(Codex32String::from_unchecksummed_string(long_hrp).is_err());
+
+        // A valid checksum and overall length, but a long HRP leaves only
+        // five characters before the checksum
+        let hrp = "x".repeat(35);
+        let mut engine = checksum::Engine::new_codex32_short();
+        engine.input_hrp(&hrp).unwrap();
+        engine.input_data_str("0test").unwrap();
+        engine.input_own_target();
+        let mut s = hrp + "10test";
+        s.extend(engine.into_residue().into_iter().map(Fe::to_char));
+        assert_eq!(s.len(), 54);
+        assert!(matches!(
+            Codex32String::from_string(s),
+            Err(Error::InvalidLength(54))
+        ));
+
+        // A valid checksum, computed with an empty HRP, but no separator
+        assert!(matches!(
+            Codex32String::from_unchecksummed_string("0testsxxxxxxxxxxxxxxxxxxxxxxxxxx".into()),
+            Err(Error::MissingSeparator)
+        ));
+        assert!(matches!(
+            Codex32String::from_string("0testsxxxxxxxxxxxxxxxxxxxxxxxxxxxxxtvaj53mashrw6".into()),
+            Err(Error::MissingSeparator)
+        ));
+    }
+
+    #[test]
+    fn lagrange() {
+        // Recover the secret of BIP vector 3 one share at a time
+        let shares = [
+            "ms13casha320zyxwvutsrqpnmlkjhgfedca2a8d0zehn8a0t",
+            "ms13cashcacdefghjklmnpqrstuvwxyz023949xq35my48dr",
+            "ms13cashd0wsedstcdcts64cd7wvy4m90lm28w4ffupqs7rm",
+        ];
+        let coeffs = lagrange_coefficients(&[Fe::A, Fe::C, Fe::D], Fe::S).unwrap();
+        assert_eq!(coeffs.iter().fold(Fe::Q, |acc, x| acc + *x), Fe::P);
+
+        let mut result = vec![Fe::Q; shares[0].len() - 3];
+        for (share, coeff) in shares.iter().zip(coeffs) {
+            let share = Codex32String::from_string(share.to_string()).unwrap();
+            for (res, fe) in result.iter_mut().zip(share.data_fes()) {
+                *res += coeff * fe;
+            }
+        }
+        assert_eq!(
+            Codex32String::from_hrp_and_data("ms", result).expose_secret(),
+            "ms13cashsllhdmn9m42vcsamx24zrxgs3qqjzqud4m0d6nln",
+        );
+
+        // Target is one of the inputs
+        assert_eq!(
+            lagrange_coefficients(&[Fe::A, Fe::C, Fe::D], Fe::C).unwrap(),
+            vec![Fe::Q, Fe::P, Fe::Q],
+        );
+
+        // Errors
+        assert!(matches!(
+            lagrange_coefficients(&[], Fe::S),
+            Err(Error::ThresholdNotPassed { .. }),
+        ));
+        assert!(matches!(
+            lagrange_coefficients(&[Fe::A, Fe::C, Fe::A], Fe::S),
+            Err(Error::RepeatedIndex(Fe::A)),
+        ));
+        assert!(matches!(
+            lagrange_coefficients(&[Fe::A, Fe::C, Fe::A], Fe::A),
+            Err(Error::RepeatedIndex(Fe::A)),
+        ));
+    }
+
+    #[test]
+    fn interpolate_upper_hrp_with_digit() {
+        // An HRP may contain digits, which have no case, so the output must be
+        // upper case because the HRP has an upper-case letter in it
+        let shares: Vec<Codex32String> = [(Fe::A, [0x11; 16]), (Fe::C, [0x22; 16])]
+            .iter()
+            .map(|(idx, seed)| {
+                let buf = Codex32Buf::from_seed("MS2", 2, "LEET", *idx, seed).unwrap();
+                Codex32String::from(&buf)
+            })
+            .collect();
+        for target in &[Fe::S, Fe::D] {
+            let result = Codex32String::interpolate_at(&shares, *target).unwrap();
+            let s = result.expose_secret();
+            assert_eq!(s, s.to_ascii_uppercase());
+            assert_eq!(Codex32String::from_string(s.into()).unwrap(), result);
+        }
+    }
+
+    #[test]
+    fn from_seed_matches_buf() {
+        // Both constructors check the identifier and choose the case the same way
+        for hrp in &["ms", "MS2", "<B>&"] {
+            let s = Codex32String::from_seed(hrp, 2, "leet", Fe::A, &[0x33; 16]).unwrap();
+            let buf = Codex32Buf::from_seed(hrp, 2, "leet", Fe::A, &[0x33; 16]).unwrap();
+            assert_eq!(s, Codex32String::from(&buf));
+        }
+        for id in &["lee", "leetx", "leeb"] {
+            assert!(Codex32String::from_seed("ms", 2, id, Fe::A, &[0x33; 16]).is_err());
+            assert!(Codex32Buf::from_seed("ms", 2, id, Fe::A, &[0x33; 16]).is_err());
+        }
+    }
 }
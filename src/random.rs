@@ -0,0 +1,222 @@
+// Rust Codex32 Library and Reference Implementation
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Randomness
+//!
+//! Generates fresh seeds and shares using a caller-supplied
+//! random number generator.
+//!
+
+use rand_core::{CryptoRng, RngCore};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+use super::{Codex32String, Error, Id};
+use crate::field::Fe;
+use alloc::vec::Vec;
+
+impl Codex32String {
+    /// Creates the `S` share of a freshly generated master seed of `seed_len`
+    /// bytes.
+    ///
+    /// BIP-93 allows seeds of 16 to 64 bytes; 16 is the usual choice. If
+    /// `id` is `None`, a random identifier is generated with [`Id::random`].
+    pub fn from_random_seed<R: RngCore + CryptoRng>(
+        hrp: &str,
+        threshold: usize,
+        id: Option<&str>,
+        seed_len: usize,
+        rng: &mut R,
+    ) -> Result<Codex32String, Error> {
+        if !(16..=64).contains(&seed_len) {
+            return Err(Error::InvalidSeedLength(seed_len));
+        }
+        let id = id_or_random(id, rng)?;
+        let mut seed = vec![0; seed_len];
+        rng.fill_bytes(&mut seed);
+        let ret = Codex32String::from_seed(hrp, threshold, id.as_str(), Fe::S, &seed);
+        #[cfg(feature = "zeroize")]
+        seed.zeroize();
+        ret
+    }
+
+    /// Splits a master seed into `n_shares` shares, using `rng` to generate
+    /// the random shares.
+    ///
+    /// Returns the shares in index order, i.e. `A`, `C`, `D`, ... and never
+    /// including the `S` share. If `id` is `None`, a random identifier is
+    /// generated with [`Id::random`].
+    pub fn split_random<R: RngCore + CryptoRng>(
+        hrp: &str,
+        threshold: usize,
+        id: Option<&str>,
+        data: &[u8],
+        n_shares: usize,
+        rng: &mut R,
+    ) -> Result<Vec<Codex32String>, Error> {
+        let id = id_or_random(id, rng)?;
+        Codex32String::split_with(hrp, threshold, id.as_str(), data, n_shares, |_, buf| {
+            rng.fill_bytes(buf)
+        })
+    }
+
+    /// Splits this share into `n_shares` contributions to a new share set,
+    /// with threshold `threshold` and identifier `id`, using `rng` to
+    /// generate the random shares.
+    ///
+    /// See [`Codex32String::reshare_deterministic`] for how the contributions
+    /// are used.
+    pub fn reshare_random<R: RngCore + CryptoRng>(
+        &self,
+        threshold: usize,
+        id: &str,
+        n_shares: usize,
+        rng: &mut R,
+    ) -> Result<Vec<Codex32String>, Error> {
+        let secret = self.relabel(threshold, id)?;
+        Codex32String::split_share_with(secret, n_shares, |_, buf| rng.fill_bytes(buf))
+    }
+
+    /// Generates delta shares, encoding a master seed of `len` zero bytes,
+    /// for refreshing the shares with the given indices, using `rng` to
+    /// generate the random shares.
+    ///
+    /// See [`Codex32String::apply_refresh_delta`] for how the delta shares
+    /// are used.
+    pub fn refresh_deltas_random<R: RngCore + CryptoRng>(
+        hrp: &str,
+        threshold: usize,
+        id: &str,
+        len: usize,
+        indices: &[Fe],
+        rng: &mut R,
+    ) -> Result<Vec<Codex32String>, Error> {
+        Codex32String::refresh_deltas_with(hrp, threshold, id, len, indices, |_, buf| {
+            rng.fill_bytes(buf)
+        })
+    }
+}
+
+/// Parses `id`, or generates a random identifier if there is none
+fn id_or_random<R: RngCore + CryptoRng>(id: Option<&str>, rng: &mut R) -> Result<Id, Error> {
+    match id {
+        Some(id) => id.parse(),
+        None => Ok(Id::random(rng)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic, and very much not cryptographic, RNG for testing
+    struct TestRng(u64);
+
+    impl RngCore for TestRng {
+        fn next_u32(&mut self) -> u32 {
+            self.next_u64() as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            // xorshift64
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            rand_core::impls::fill_bytes_via_next(self, dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for TestRng {}
+
+    #[test]
+    fn random_split() {
+        let mut rng = TestRng(0x1234_5678_9abc_def0);
+
+        let secret = Codex32String::from_random_seed("ms", 2, None, 16, &mut rng).unwrap();
+        let data = secret.parts().data();
+        assert_eq!(data.len(), 16);
+        let id = secret.parts().id;
+
+        let shares = Codex32String::split_random("ms", 2, Some(id), &data, 4, &mut rng).unwrap();
+        assert_eq!(shares.len(), 4);
+        for i in 0..4 {
+            assert_eq!(shares[i].parts().id, id);
+            for j in i + 1..4 {
+                let pair = [shares[i].clone(), shares[j].clone()];
+                assert_eq!(Codex32String::interpolate_at(&pair, Fe::S).unwrap(), secret);
+            }
+        }
+        // A second split gives different shares
+        let again = Codex32String::split_random("ms", 2, Some(id), &data, 4, &mut rng).unwrap();
+        assert_ne!(shares, again);
+        // Without an identifier, every share gets the same random one
+        let fresh = Codex32String::split_random("ms", 2, None, &data, 4, &mut rng).unwrap();
+        let fresh_id = fresh[0].parts().id;
+        assert!(fresh_id.parse::<Id>().is_ok());
+        assert!(fresh.iter().all(|share| share.parts().id == fresh_id));
+        assert_eq!(
+            Codex32String::interpolate_at(&fresh[1..3], Fe::S)
+                .unwrap()
+                .parts()
+                .data(),
+            data,
+        );
+
+        // Re-share shares A and C into a 3-of-3 set
+        let contributions: Vec<Vec<Codex32String>> = shares[..2]
+            .iter()
+            .map(|share| share.reshare_random(3, "cafe", 3, &mut rng).unwrap())
+            .collect();
+        let new: Vec<Codex32String> = (0..3)
+            .map(|i| {
+                Codex32String::combine_reshares(&[
+                    (Fe::A, contributions[0][i].clone()),
+                    (Fe::C, contributions[1][i].clone()),
+                ])
+                .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            Codex32String::interpolate_at(&new, Fe::S)
+                .unwrap()
+                .parts()
+                .data(),
+            data,
+        );
+    }
+
+    #[test]
+    fn random_seed_length() {
+        let mut rng = TestRng(1);
+        assert!(matches!(
+            Codex32String::from_random_seed("ms", 0, Some("test"), 15, &mut rng),
+            Err(Error::InvalidSeedLength(15)),
+        ));
+        assert!(matches!(
+            Codex32String::from_random_seed("ms", 0, Some("test"), 65, &mut rng),
+            Err(Error::InvalidSeedLength(65)),
+        ));
+        let long = Codex32String::from_random_seed("ms", 0, Some("test"), 64, &mut rng).unwrap();
+        assert_eq!(long.parts().data().len(), 64);
+    }
+}
@@ -0,0 +1,274 @@
+// Rust Codex32 Library and Reference Implementation
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Incremental Recovery
+//!
+//! Interpolates a set of shares which arrive one at a time, so that they
+//! never need to be held in memory together.
+//!
+
+use super::{lagrange_coefficients, Codex32String, Error};
+use crate::field::Fe;
+use alloc::{string::String, vec::Vec};
+use core::{fmt, mem};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+/// The parts of the first share which every later share must match
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+struct Header {
+    hrp: String,
+    threshold: usize,
+    id: String,
+    len: usize,
+}
+
+/// An engine which accepts shares one at a time and accumulates their
+/// weighted sum, producing the share at some target index once every share
+/// has been entered
+///
+/// The indices of the shares have to be declared up front, since the
+/// weight given to each share depends on all of them. Only the running sum
+/// is retained, never the shares themselves.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Recoverer {
+    indices: Vec<Fe>,
+    coeffs: Vec<Fe>,
+    seen: Vec<bool>,
+    header: Option<Header>,
+    result: Vec<Fe>,
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Recoverer {
+    fn drop(&mut self) {
+        self.result.zeroize();
+    }
+}
+
+impl fmt::Debug for Recoverer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Recoverer")
+            .field("indices", &self.indices)
+            .field("seen", &self.seen)
+            .field("header", &self.header)
+            .field("result", &"<redacted>")
+            .finish()
+    }
+}
+
+impl Recoverer {
+    /// Creates a new recoverer, which will derive the share at `target`
+    /// from the shares with the given indices.
+    ///
+    /// Using the index `Fe::S` will recover the master seed.
+    pub fn new(indices: &[Fe], target: Fe) -> Result<Recoverer, Error> {
+        Ok(Recoverer {
+            indices: indices.to_vec(),
+            coeffs: lagrange_coefficients(indices, target)?,
+            seen: vec![false; indices.len()],
+            header: None,
+            result: vec![],
+        })
+    }
+
+    /// Adds a share to the recoverer.
+    ///
+    /// The share must have one of the indices declared at construction time,
+    /// must not already have been added, and must have the same HRP,
+    /// threshold, identifier and length as the first share that was added.
+    pub fn add_share(&mut self, share: &Codex32String) -> Result<(), Error> {
+        let parts = share.parts();
+        let pos = match self.indices.iter().position(|i| *i == parts.share_index) {
+            Some(pos) => pos,
+            None => return Err(Error::UnexpectedIndex(parts.share_index)),
+        };
+        if self.seen[pos] {
+            return Err(Error::RepeatedIndex(parts.share_index));
+        }
+
+        match self.header {
+            Some(ref header) => {
+                if header.len != share.0.len() {
+                    return Err(Error::MismatchedLength(header.len, share.0.len()));
+                }
+                if header.hrp != parts.hrp {
+                    return Err(Error::MismatchedHrp(header.hrp.clone(), parts.hrp.into()));
+                }
+                if header.threshold != parts.threshold {
+                    return Err(Error::MismatchedThreshold(
+                        header.threshold,
+                        parts.threshold,
+                    ));
+                }
+                if header.id != parts.id {
+                    return Err(Error::MismatchedId(header.id.clone(), parts.id.into()));
+                }
+            }
+            None => {
+                if parts.threshold > self.indices.len() {
+                    return Err(Error::ThresholdNotPassed {
+                        threshold: parts.threshold,
+                        n_shares: self.indices.len(),
+                    });
+                }
+                self.header = Some(Header {
+                    hrp: parts.hrp.into(),
+                    threshold: parts.threshold,
+                    id: parts.id.into(),
+                    len: share.0.len(),
+                });
+                self.result = vec![Fe::Q; share.0.len() - parts.hrp.len() - 1];
+            }
+        }
+
+        let coeff = self.coeffs[pos];
+        for (res, fe) in self.result.iter_mut().zip(share.data_fes()) {
+            *res += coeff.ct_mul(fe);
+        }
+        self.seen[pos] = true;
+        Ok(())
+    }
+
+    /// The number of shares which have been added so far
+    pub fn n_shares(&self) -> usize {
+        self.seen.iter().filter(|seen| **seen).count()
+    }
+
+    /// Whether every declared share has been added
+    pub fn is_complete(&self) -> bool {
+        self.seen.iter().all(|seen| *seen)
+    }
+
+    /// Outputs the share at the target index.
+    ///
+    /// Fails if any of the declared shares have not yet been added.
+    pub fn finish(mut self) -> Result<Codex32String, Error> {
+        match self.header {
+            Some(ref header) if self.is_complete() => Ok(Codex32String::from_hrp_and_data(
+                &header.hrp,
+                mem::take(&mut self.result),
+            )),
+            _ => Err(Error::ThresholdNotPassed {
+                threshold: self.indices.len(),
+                n_shares: self.n_shares(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn share(s: &str) -> Codex32String {
+        Codex32String::from_string(s.into()).unwrap()
+    }
+
+    #[test]
+    fn recover_incrementally() {
+        let shares = [
+            share("ms13casha320zyxwvutsrqpnmlkjhgfedca2a8d0zehn8a0t"),
+            share("ms13cashcacdefghjklmnpqrstuvwxyz023949xq35my48dr"),
+            share("ms13cashd0wsedstcdcts64cd7wvy4m90lm28w4ffupqs7rm"),
+        ];
+
+        let mut rec = Recoverer::new(&[Fe::D, Fe::A, Fe::C], Fe::S).unwrap();
+        // Order of arrival does not matter
+        for share in &shares {
+            assert!(!rec.is_complete());
+            rec.add_share(share).unwrap();
+        }
+        assert!(rec.is_complete());
+        assert_eq!(
+            rec.finish().unwrap(),
+            Codex32String::interpolate_at(&shares, Fe::S).unwrap(),
+        );
+
+        // Derive a non-secret share, with the target among the inputs
+        let mut rec = Recoverer::new(&[Fe::A, Fe::C, Fe::D], Fe::C).unwrap();
+        for share in &shares {
+            rec.add_share(share).unwrap();
+        }
+        assert_eq!(rec.finish().unwrap(), shares[1]);
+
+        // An upper-case HRP with a digit in it gives an upper-case result
+        let shares = [
+            share("MS212LEETAZYG3ZYG3ZYG3ZYG3ZYG3ZYG3ZYVZU0MGZW35HGA"),
+            share("MS212LEETCYG3ZYG3ZYG3ZYG3ZYG3ZYG3ZYGHCH5Y4RNGDVFE"),
+        ];
+        let mut rec = Recoverer::new(&[Fe::A, Fe::C], Fe::S).unwrap();
+        for share in &shares {
+            rec.add_share(share).unwrap();
+        }
+        let secret = rec.finish().unwrap();
+        assert_eq!(
+            Codex32String::from_string(secret.expose_secret().into()).unwrap(),
+            secret,
+        );
+        assert_eq!(
+            secret,
+            Codex32String::interpolate_at(&shares, Fe::S).unwrap()
+        );
+    }
+
+    #[test]
+    fn recover_errors() {
+        let shares = [
+            share("ms13casha320zyxwvutsrqpnmlkjhgfedca2a8d0zehn8a0t"),
+            share("ms13cashcacdefghjklmnpqrstuvwxyz023949xq35my48dr"),
+            share("ms13cashd0wsedstcdcts64cd7wvy4m90lm28w4ffupqs7rm"),
+        ];
+
+        assert!(matches!(
+            Recoverer::new(&[Fe::A, Fe::A], Fe::S),
+            Err(Error::RepeatedIndex(Fe::A)),
+        ));
+
+        // Too few declared indices for the threshold
+        let mut rec = Recoverer::new(&[Fe::A, Fe::C], Fe::S).unwrap();
+        assert!(matches!(
+            rec.add_share(&shares[0]),
+            Err(Error::ThresholdNotPassed {
+                threshold: 3,
+                n_shares: 2
+            }),
+        ));
+
+        let mut rec = Recoverer::new(&[Fe::A, Fe::C, Fe::E], Fe::S).unwrap();
+        rec.add_share(&shares[0]).unwrap();
+        assert!(matches!(
+            rec.add_share(&shares[0]),
+            Err(Error::RepeatedIndex(Fe::A)),
+        ));
+        assert!(matches!(
+            rec.add_share(&shares[2]),
+            Err(Error::UnexpectedIndex(Fe::D)),
+        ));
+        let other_id = share("MS12NAMECACDEFGHJKLMNPQRSTUVWXYZ023FTR2GDZMPY6PN");
+        assert!(matches!(
+            rec.add_share(&other_id),
+            Err(Error::MismatchedHrp(..)),
+        ));
+        rec.add_share(&shares[1]).unwrap();
+        assert_eq!(rec.n_shares(), 2);
+        assert!(matches!(
+            rec.finish(),
+            Err(Error::ThresholdNotPassed {
+                threshold: 3,
+                n_shares: 2
+            }),
+        ));
+    }
+}
@@ -0,0 +1,257 @@
+// Rust Codex32 Library and Reference Implementation
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Refreshing
+//!
+//! Replaces a set of shares by a fresh set for the same master seed and
+//! threshold, so that shares which were compromised before the refresh are
+//! useless afterwards.
+//!
+//! The new set always has a new identifier, so that old and new shares
+//! cannot be accidentally combined; any attempt to do so fails with
+//! [`Error::MismatchedId`].
+//!
+//! Shares can be refreshed in one of two ways. The first is to re-share
+//! the existing set (see [`Codex32String::refresh_set`]). The second is for
+//! a dealer to split a master seed of all zeroes into "delta shares", and
+//! give one to each share holder, who adds it to their own share (see
+//! [`Codex32String::apply_refresh_delta`]). Since the delta shares encode
+//! zero, the refreshed shares encode the same seed as the old ones, and the
+//! dealer never sees any of the shares.
+//!
+
+use super::{Codex32String, Error};
+use crate::field::Fe;
+use crate::split::deterministic_fill;
+use alloc::vec::Vec;
+
+/// Domain separation tag for deterministic delta shares
+const DELTA_TAG: &[u8] = b"codex32 refresh delta";
+
+impl Codex32String {
+    /// Refreshes a set of shares into `n_shares` new shares with the same
+    /// threshold and the new identifier `id`.
+    ///
+    /// See [`Codex32String::reshare_set`], which this calls.
+    pub fn refresh_set(
+        shares: &[Codex32String],
+        id: &str,
+        extra_entropy: &[u8],
+        n_shares: usize,
+    ) -> Result<Vec<Codex32String>, Error> {
+        let parts = match shares.first() {
+            Some(share) => share.parts(),
+            None => {
+                return Err(Error::ThresholdNotPassed {
+                    threshold: 1,
+                    n_shares: 0,
+                })
+            }
+        };
+        if parts.id.eq_ignore_ascii_case(id) {
+            return Err(Error::UnchangedId(id.into()));
+        }
+        Codex32String::reshare_set(shares, parts.threshold, id, extra_entropy, n_shares)
+    }
+
+    /// Generates delta shares, encoding a master seed of `len` zero bytes,
+    /// for refreshing the shares with the given indices, deriving them
+    /// deterministically from `entropy`.
+    ///
+    /// `entropy` must be secret and uniformly random, e.g. the output of
+    /// [`crate::dice::DiceEntropy::into_seed`]; anybody who knows it can
+    /// compute the delta shares, and hence undo the refresh.
+    ///
+    /// The delta shares are returned in the same order as `indices`, and
+    /// have the same HRP, threshold and length as the shares to refresh,
+    /// and the new identifier `id`.
+    pub fn refresh_deltas_deterministic(
+        hrp: &str,
+        threshold: usize,
+        id: &str,
+        len: usize,
+        entropy: &[u8],
+        indices: &[Fe],
+    ) -> Result<Vec<Codex32String>, Error> {
+        Codex32String::refresh_deltas_with(
+            hrp,
+            threshold,
+            id,
+            len,
+            indices,
+            deterministic_fill(DELTA_TAG, hrp, entropy, &[]),
+        )
+    }
+
+    /// Generates delta shares for the given indices, calling `fill` to
+    /// obtain the payload of each of the random shares of the zero split.
+    pub(crate) fn refresh_deltas_with<F>(
+        hrp: &str,
+        threshold: usize,
+        id: &str,
+        len: usize,
+        indices: &[Fe],
+        fill: F,
+    ) -> Result<Vec<Codex32String>, Error>
+    where
+        F: FnMut(&str, &mut [u8]),
+    {
+        let zero = vec![0; len];
+        let split = Codex32String::split_with(hrp, threshold, id, &zero, threshold, fill)?;
+        let mut ret = Vec::with_capacity(indices.len());
+        for idx in indices {
+            if *idx == Fe::S {
+                return Err(Error::InvalidShareIndex(Fe::S));
+            }
+            ret.push(Codex32String::interpolate_at(&split, *idx)?);
+        }
+        Ok(ret)
+    }
+
+    /// Refreshes this share by adding a delta share to it.
+    ///
+    /// The delta share must have the same HRP, threshold, share index and
+    /// length as this share, and a different identifier. The result has the
+    /// identifier of the delta share.
+    pub fn apply_refresh_delta(&self, delta: &Codex32String) -> Result<Codex32String, Error> {
+        let parts = self.parts();
+        let delta_parts = delta.parts();
+        if self.0.len() != delta.0.len() {
+            return Err(Error::MismatchedLength(self.0.len(), delta.0.len()));
+        }
+        // Compare the HRPs exactly, as `check_compatible` does for
+        // interpolation; the identifiers must differ, so it cannot be used
+        if parts.hrp != delta_parts.hrp {
+            return Err(Error::MismatchedHrp(
+                parts.hrp.into(),
+                delta_parts.hrp.into(),
+            ));
+        }
+        if parts.threshold != delta_parts.threshold {
+            return Err(Error::MismatchedThreshold(
+                parts.threshold,
+                delta_parts.threshold,
+            ));
+        }
+        if parts.share_index != delta_parts.share_index {
+            return Err(Error::MismatchedShareIndex(
+                parts.share_index,
+                delta_parts.share_index,
+            ));
+        }
+        if parts.id == delta_parts.id {
+            return Err(Error::UnchangedId(delta_parts.id.into()));
+        }
+
+        // Take the header from the delta share and add the payloads. The
+        // checksum is affine rather than linear, so it has to be recomputed.
+        let payload_len = parts.payload.len();
+        let mut data: Vec<Fe> = delta.data_fes().take(6 + payload_len).collect();
+        for (res, fe) in data[6..].iter_mut().zip(self.data_fes().skip(6)) {
+            *res += fe;
+        }
+        Codex32String::checksummed(&parts.hrp.to_ascii_lowercase(), self.0.len() > 93, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refresh() {
+        let seed = [0x42; 16];
+        let secret = Codex32String::from_seed("ms", 2, "cafe", Fe::S, &seed).unwrap();
+        let old = Codex32String::split_deterministic("ms", 2, "leet", &seed, &[], 3).unwrap();
+
+        let new = Codex32String::refresh_set(&old[..2], "cafe", &[], 3).unwrap();
+        assert_ne!(old[0].parts().payload, new[0].parts().payload);
+        assert_eq!(Codex32String::interpolate_at(&new, Fe::S).unwrap(), secret);
+        assert!(matches!(
+            Codex32String::interpolate_at(&[new[0].clone(), old[1].clone()], Fe::S),
+            Err(Error::MismatchedId(..)),
+        ));
+
+        assert!(matches!(
+            Codex32String::refresh_set(&old[..2], "LEET", &[], 3),
+            Err(Error::UnchangedId(..)),
+        ));
+    }
+
+    #[test]
+    fn refresh_deltas() {
+        let seed = [0x42; 16];
+        let secret = Codex32String::from_seed("ms", 3, "cafe", Fe::S, &seed).unwrap();
+        let old = Codex32String::split_deterministic("ms", 3, "leet", &seed, &[], 5).unwrap();
+        let indices: Vec<Fe> = old.iter().map(|s| s.parts().share_index).collect();
+
+        let deltas =
+            Codex32String::refresh_deltas_deterministic("ms", 3, "cafe", 16, &[7; 32], &indices)
+                .unwrap();
+        assert_eq!(deltas.len(), 5);
+        // The delta shares are themselves a split of zero
+        assert_eq!(
+            Codex32String::interpolate_at(&deltas[..3], Fe::S).unwrap(),
+            Codex32String::from_seed("ms", 3, "cafe", Fe::S, &[0; 16]).unwrap(),
+        );
+
+        let new: Vec<Codex32String> = old
+            .iter()
+            .zip(&deltas)
+            .map(|(share, delta)| share.apply_refresh_delta(delta).unwrap())
+            .collect();
+        for (old, new) in old.iter().zip(&new) {
+            assert_eq!(new.parts().share_index, old.parts().share_index);
+            assert_eq!(new.parts().id, "cafe");
+            assert_ne!(new.parts().payload, old.parts().payload);
+        }
+        assert_eq!(
+            Codex32String::interpolate_at(&new[..3], Fe::S).unwrap(),
+            secret
+        );
+        assert_eq!(
+            Codex32String::interpolate_at(&new[2..], Fe::S).unwrap(),
+            secret
+        );
+        assert!(matches!(
+            Codex32String::interpolate_at(&[new[0].clone(), new[1].clone(), old[2].clone()], Fe::S),
+            Err(Error::MismatchedId(..)),
+        ));
+
+        // Deltas must match the share they are applied to
+        assert!(matches!(
+            old[0].apply_refresh_delta(&deltas[1]),
+            Err(Error::MismatchedShareIndex(..)),
+        ));
+        assert!(matches!(
+            new[0].apply_refresh_delta(&deltas[0]),
+            Err(Error::UnchangedId(..)),
+        ));
+        // HRPs must match exactly, as for interpolation
+        let upper =
+            Codex32String::from_string(old[0].expose_secret().to_ascii_uppercase()).unwrap();
+        assert!(matches!(
+            upper.apply_refresh_delta(&deltas[0]),
+            Err(Error::MismatchedHrp(..)),
+        ));
+        assert!(matches!(
+            Codex32String::interpolate_at(&[upper, old[1].clone(), old[2].clone()], Fe::S),
+            Err(Error::MismatchedHrp(..)),
+        ));
+        assert!(matches!(
+            Codex32String::refresh_deltas_deterministic("ms", 3, "cafe", 16, &[7; 32], &[Fe::S]),
+            Err(Error::InvalidShareIndex(Fe::S)),
+        ));
+    }
+}
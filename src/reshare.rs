@@ -0,0 +1,319 @@
+// Rust Codex32 Library and Reference Implementation
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Re-sharing
+//!
+//! Moves a secret from one set of shares to another, with a possibly
+//! different threshold, identifier or number of shares, without the master
+//! seed ever being reconstructed.
+//!
+//! Each holder of one of `k` old shares splits their own share, as if it
+//! were a master seed, into a set of "contributions" with the new threshold
+//! and identifier, and sends one contribution to each new share holder.
+//! Each new share is then a weighted sum of the `k` contributions it
+//! received, where the weights are the Lagrange coefficients which would
+//! have recovered the seed from the old shares. Interpolation is linear, so
+//! the new shares lie on a polynomial whose value at `S` is the master seed,
+//! but at no point does any one party hold more than a single old share.
+//!
+
+use super::{check_compatible, lagrange_coefficients, Codex32String, Error, Id};
+use crate::checksum;
+use crate::field::Fe;
+use crate::split::deterministic_fill;
+use alloc::vec::Vec;
+
+/// Domain separation tag for deterministic re-sharing
+const RESHARE_TAG: &[u8] = b"codex32 deterministic reshare";
+
+impl Codex32String {
+    /// Splits this share into `n_shares` contributions to a new share set,
+    /// with threshold `threshold` and identifier `id`, deriving the random
+    /// shares deterministically from this share.
+    ///
+    /// The contributions are returned in index order, i.e. `A`, `C`, `D`,
+    /// ...; each should be sent to the holder of the new share with that
+    /// index, who combines them using [`Codex32String::combine_reshares`].
+    /// The HRP of the new set is the same as that of this share.
+    ///
+    /// As with [`Codex32String::split_deterministic`], anybody holding this
+    /// share can recompute its contributions unless `extra_entropy` is
+    /// supplied and kept secret.
+    pub fn reshare_deterministic(
+        &self,
+        threshold: usize,
+        id: &str,
+        extra_entropy: &[u8],
+        n_shares: usize,
+    ) -> Result<Vec<Codex32String>, Error> {
+        let secret = self.relabel(threshold, id)?;
+        let hrp = self.parts().hrp.to_ascii_lowercase();
+        let key = self.parts().data();
+        Codex32String::split_share_with(
+            secret,
+            n_shares,
+            deterministic_fill(RESHARE_TAG, &hrp, &key, extra_entropy),
+        )
+    }
+
+    /// Combines the contributions to a single new share, one from each of
+    /// the old shares, given as pairs of the index of the old share and the
+    /// contribution derived from it.
+    ///
+    /// There must be enough contributions to pass the threshold of the old
+    /// share set. This cannot be checked here, since the contributions only
+    /// carry the header of the new share set; too few contributions will
+    /// produce a share which is consistent with nothing.
+    pub fn combine_reshares(contributions: &[(Fe, Codex32String)]) -> Result<Codex32String, Error> {
+        let old_indices: Vec<Fe> = contributions.iter().map(|(idx, _)| *idx).collect();
+        let coeffs = lagrange_coefficients(&old_indices, Fe::S)?;
+
+        let first = &contributions[0].1;
+        let first_parts = first.parts();
+        for (_, share) in contributions {
+            let parts = share.parts();
+            check_compatible(&first.0, &first_parts, &share.0, &parts)?;
+            if first_parts.share_index != parts.share_index {
+                return Err(Error::MismatchedShareIndex(
+                    first_parts.share_index,
+                    parts.share_index,
+                ));
+            }
+        }
+
+        // The coefficients sum to 1, so the header of the result is the
+        // common header of the contributions, and its checksum is valid.
+        let mut result = vec![Fe::Q; first.0.len() - first_parts.hrp.len() - 1];
+        for ((_, share), coeff) in contributions.iter().zip(coeffs) {
+            for (res, fe) in result.iter_mut().zip(share.data_fes()) {
+                *res += coeff.ct_mul(fe);
+            }
+        }
+        Ok(Codex32String::from_hrp_and_data(first_parts.hrp, result))
+    }
+
+    /// Re-shares a set of shares into `n_shares` new shares with threshold
+    /// `threshold` and identifier `id`, by running the re-sharing protocol
+    /// locally.
+    ///
+    /// Each old share is used only to produce its own contributions, so the
+    /// master seed is never reconstructed, although all the shares are held
+    /// by the caller. To keep the shares apart, have each holder call
+    /// [`Codex32String::reshare_deterministic`] and each new holder call
+    /// [`Codex32String::combine_reshares`] instead.
+    pub fn reshare_set(
+        shares: &[Codex32String],
+        threshold: usize,
+        id: &str,
+        extra_entropy: &[u8],
+        n_shares: usize,
+    ) -> Result<Vec<Codex32String>, Error> {
+        let indices = Codex32String::interpolation_indices(shares)?;
+        // Check for repeated indices before doing any work
+        lagrange_coefficients(&indices, Fe::S)?;
+
+        let mut contributions = Vec::with_capacity(shares.len());
+        for share in shares {
+            contributions.push(share.reshare_deterministic(
+                threshold,
+                id,
+                extra_entropy,
+                n_shares,
+            )?);
+        }
+        let mut ret = Vec::with_capacity(n_shares);
+        for i in 0..n_shares {
+            let for_i: Vec<(Fe, Codex32String)> = indices
+                .iter()
+                .zip(&contributions)
+                .map(|(idx, contribs)| (*idx, contribs[i].clone()))
+                .collect();
+            ret.push(Codex32String::combine_reshares(&for_i)?);
+        }
+        Ok(ret)
+    }
+
+    /// Copies this share with its header replaced by that of the `S` share
+    /// of a share set with the given threshold and identifier, recomputing
+    /// the checksum. The result is always lowercase.
+    pub(crate) fn relabel(&self, threshold: usize, id: &str) -> Result<Codex32String, Error> {
+        let id: Id = id.parse()?;
+        let k = match threshold {
+            0 | 2..=9 => Fe::from_char(char::from(b'0' + threshold as u8)).unwrap(), // unwrap ok since a digit
+            x => return Err(Error::InvalidThresholdN(x)),
+        };
+        let hrp = self.parts().hrp.to_ascii_lowercase();
+
+        let mut data = vec![k];
+        data.extend(id.as_str().chars().map(|c| Fe::from_char(c).unwrap())); // unwrap ok since id is valid
+        data.push(Fe::S);
+        // unwrap ok since string is valid
+        data.extend(
+            self.parts()
+                .payload
+                .chars()
+                .map(|c| Fe::from_char(c).unwrap()),
+        );
+        Codex32String::checksummed(&hrp, self.0.len() > 93, data)
+    }
+
+    /// Assembles a string from an HRP and a data part without a checksum,
+    /// computing and appending the short or long checksum.
+    ///
+    /// The HRP must be lowercase.
+    pub(crate) fn checksummed(
+        hrp: &str,
+        long: bool,
+        mut data: Vec<Fe>,
+    ) -> Result<Codex32String, Error> {
+        let mut checksum = if long {
+            checksum::Engine::new_codex32_long()
+        } else {
+            checksum::Engine::new_codex32_short()
+        };
+        checksum.input_hrp(hrp)?;
+        for fe in &data {
+            checksum.input_fe(*fe);
+        }
+        checksum.input_own_target();
+        data.extend(checksum.into_residue());
+        Ok(Codex32String::from_hrp_and_data(hrp, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reshare() {
+        #[rustfmt::skip]
+        let seed = [
+            0xff, 0xee, 0xdd, 0xcc, 0xbb, 0xaa, 0x99, 0x88,
+            0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11, 0x00,
+        ];
+        let secret = Codex32String::from_seed("ms", 3, "leet", Fe::S, &seed).unwrap();
+        let old = Codex32String::split_deterministic("ms", 3, "leet", &seed, &[], 5).unwrap();
+        let subset = [old[1].clone(), old[3].clone(), old[4].clone()];
+
+        // Move from 3-of-5 to 2-of-4 with a new identifier
+        let new = Codex32String::reshare_set(&subset, 2, "cafe", &[], 4).unwrap();
+        assert_eq!(new.len(), 4);
+        let new_secret = Codex32String::from_seed("ms", 2, "cafe", Fe::S, &seed).unwrap();
+        for i in 0..4 {
+            assert_eq!(new[i].parts().id, "cafe");
+            assert_eq!(new[i].parts().threshold, 2);
+            for j in i + 1..4 {
+                let pair = [new[i].clone(), new[j].clone()];
+                assert_eq!(
+                    Codex32String::interpolate_at(&pair, Fe::S).unwrap(),
+                    new_secret
+                );
+            }
+        }
+        // The old and new sets cannot be mixed
+        assert!(matches!(
+            Codex32String::interpolate_at(&[new[0].clone(), old[0].clone()], Fe::S),
+            Err(Error::MismatchedThreshold(2, 3)),
+        ));
+
+        // Running the protocol by hand gives the same result
+        let contributions: Vec<Vec<Codex32String>> = subset
+            .iter()
+            .map(|share| share.reshare_deterministic(2, "cafe", &[], 4).unwrap())
+            .collect();
+        for i in 0..4 {
+            let for_i: Vec<(Fe, Codex32String)> = subset
+                .iter()
+                .zip(&contributions)
+                .map(|(share, contribs)| (share.parts().share_index, contribs[i].clone()))
+                .collect();
+            assert_eq!(Codex32String::combine_reshares(&for_i).unwrap(), new[i]);
+        }
+
+        // Re-sharing again, back to the original threshold and identifier,
+        // recovers the original secret
+        let back = Codex32String::reshare_set(&new[2..], 3, "leet", &[], 3).unwrap();
+        assert_eq!(Codex32String::interpolate_at(&back, Fe::S).unwrap(), secret);
+    }
+
+    #[test]
+    fn reshare_upper_hrp_with_digit() {
+        let old: Vec<Codex32String> = [
+            "MS212LEETAZYG3ZYG3ZYG3ZYG3ZYG3ZYG3ZYVZU0MGZW35HGA",
+            "MS212LEETCYG3ZYG3ZYG3ZYG3ZYG3ZYG3ZYGHCH5Y4RNGDVFE",
+        ]
+        .iter()
+        .map(|s| Codex32String::from_string((*s).into()).unwrap())
+        .collect();
+        // Contributions are lowercase; combining upper-case ones must give
+        // an upper-case share, although the HRP has a digit in it
+        let contributions: Vec<Vec<Codex32String>> = old
+            .iter()
+            .map(|share| share.reshare_deterministic(2, "cafe", &[], 2).unwrap())
+            .collect();
+        let for_a: Vec<(Fe, Codex32String)> = old
+            .iter()
+            .zip(&contributions)
+            .map(|(share, contribs)| {
+                let upper = contribs[0].expose_secret().to_ascii_uppercase();
+                (
+                    share.parts().share_index,
+                    Codex32String::from_string(upper).unwrap(),
+                )
+            })
+            .collect();
+        let new = Codex32String::combine_reshares(&for_a).unwrap();
+        assert_eq!(
+            new.expose_secret(),
+            new.expose_secret().to_ascii_uppercase()
+        );
+        assert_eq!(
+            Codex32String::from_string(new.expose_secret().into()).unwrap(),
+            new,
+        );
+    }
+
+    #[test]
+    fn reshare_errors() {
+        let seed = [0x55; 16];
+        let old = Codex32String::split_deterministic("ms", 2, "leet", &seed, &[], 3).unwrap();
+        assert!(matches!(
+            Codex32String::reshare_set(&old[..1], 2, "cafe", &[], 3),
+            Err(Error::ThresholdNotPassed {
+                threshold: 2,
+                n_shares: 1
+            }),
+        ));
+        assert!(matches!(
+            Codex32String::reshare_set(&old, 1, "cafe", &[], 3),
+            Err(Error::InvalidThresholdN(1)),
+        ));
+        assert!(matches!(
+            Codex32String::reshare_set(&old, 2, "caf", &[], 3),
+            Err(Error::IdNotLength4(3)),
+        ));
+
+        let a = old[0].reshare_deterministic(2, "cafe", &[], 3).unwrap();
+        let c = old[1].reshare_deterministic(2, "cafe", &[], 3).unwrap();
+        assert!(matches!(
+            Codex32String::combine_reshares(&[(Fe::A, a[0].clone()), (Fe::C, c[1].clone())]),
+            Err(Error::MismatchedShareIndex(Fe::A, Fe::C)),
+        ));
+        assert!(matches!(
+            Codex32String::combine_reshares(&[(Fe::A, a[0].clone()), (Fe::A, c[0].clone())]),
+            Err(Error::RepeatedIndex(Fe::A)),
+        ));
+    }
+}
@@ -0,0 +1,307 @@
+// Rust Codex32 Library and Reference Implementation
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Splitting
+//!
+//! Splits a master seed into a set of shares.
+//!
+//! A split with threshold k consists of the `S` share, which encodes the
+//! master seed, together with k-1 shares whose payloads are chosen at random.
+//! These are the shares with the first k-1 indices, in alphabetical order,
+//! skipping `S` (so `A`, `C`, `D`, and so on). Every other share is derived
+//! from these by interpolation.
+//!
+
+use bitcoin_hashes::{sha256, Hash, HashEngine, Hmac, HmacEngine};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+use super::{Codex32String, Error};
+use crate::field::Fe;
+use alloc::{string::String, vec::Vec};
+
+/// Domain separation tag for deterministic share generation
+const DETERMINISTIC_TAG: &[u8] = b"codex32 deterministic share";
+
+/// The share indices, in the order in which they are assigned to shares
+fn share_indices() -> impl Iterator<Item = Fe> {
+    Fe::iter_alpha().filter(|fe| *fe != Fe::S)
+}
+
+impl Codex32String {
+    /// Splits a master seed into `n_shares` shares, deriving the random
+    /// shares deterministically from the seed and its header.
+    ///
+    /// Identical inputs always produce identical shares, so a split can be
+    /// reproduced and audited. The payload of the random share with index
+    /// `c` is the first `data.len()` bytes of the concatenation of
+    ///
+    /// ```text
+    /// HMAC-SHA256(key = data, msg = "codex32 deterministic share" || 0x00
+    ///                               || hrp || 0x00 || threshold || id || c
+    ///                               || len(extra_entropy) || extra_entropy || i)
+    /// ```
+    ///
+    /// for `i = 0, 1, ...`, where `threshold`, `id` and `c` are the lowercase
+    /// ASCII characters from the share header, `len(extra_entropy)` is a
+    /// 64-bit big-endian integer and `i` is a single byte.
+    ///
+    /// Since the random shares are a function of the seed, anybody holding
+    /// the seed can recompute them. Supplying `extra_entropy`, and keeping it
+    /// secret, prevents this; re-running the split then requires the same
+    /// extra entropy.
+    ///
+    /// Returns the shares in index order, i.e. `A`, `C`, `D`, ... and never
+    /// including the `S` share.
+    pub fn split_deterministic(
+        hrp: &str,
+        threshold: usize,
+        id: &str,
+        data: &[u8],
+        extra_entropy: &[u8],
+        n_shares: usize,
+    ) -> Result<Vec<Codex32String>, Error> {
+        Codex32String::split_with(
+            hrp,
+            threshold,
+            id,
+            data,
+            n_shares,
+            deterministic_fill(DETERMINISTIC_TAG, hrp, data, extra_entropy),
+        )
+    }
+
+    /// Splits a master seed into `n_shares` shares, calling `fill` to obtain
+    /// the payload of each of the random shares.
+    ///
+    /// `fill` is given the header of the share (threshold, identifier and
+    /// share index) and a buffer of the same length as `data` to fill.
+    pub(crate) fn split_with<F>(
+        hrp: &str,
+        threshold: usize,
+        id: &str,
+        data: &[u8],
+        n_shares: usize,
+        fill: F,
+    ) -> Result<Vec<Codex32String>, Error>
+    where
+        F: FnMut(&str, &mut [u8]),
+    {
+        // This also checks that the threshold and id are valid.
+        let secret = Codex32String::from_seed(hrp, threshold, id, Fe::S, data)?;
+        Codex32String::split_share_with(secret, n_shares, fill)
+    }
+
+    /// Splits a share, treated as the `S` share of a new split, into
+    /// `n_shares` shares, calling `fill` to obtain the payload of each of
+    /// the random shares.
+    ///
+    /// The new shares take their HRP, threshold and identifier from the
+    /// header of `secret`.
+    pub(crate) fn split_share_with<F>(
+        secret: Codex32String,
+        n_shares: usize,
+        mut fill: F,
+    ) -> Result<Vec<Codex32String>, Error>
+    where
+        F: FnMut(&str, &mut [u8]),
+    {
+        let (hrp, threshold, id, len) = {
+            let parts = secret.parts();
+            let hrp = String::from(parts.hrp);
+            let id = String::from(parts.id);
+            (hrp, parts.threshold, id, parts.payload.len() * 5 / 8)
+        };
+        if threshold == 0 {
+            return Err(Error::InvalidThresholdN(0));
+        }
+        if n_shares < threshold {
+            return Err(Error::ThresholdNotPassed {
+                threshold,
+                n_shares,
+            });
+        }
+        if n_shares > 31 {
+            return Err(Error::TooManyShares(n_shares));
+        }
+
+        let mut buf = vec![0; len];
+        let mut shares = Vec::with_capacity(n_shares);
+        for idx in share_indices().take(threshold - 1) {
+            let header = format!("{}{}{}", threshold, id, idx);
+            fill(&header, &mut buf);
+            shares.push(Codex32String::from_seed(&hrp, threshold, &id, idx, &buf)?);
+        }
+        #[cfg(feature = "zeroize")]
+        buf.zeroize();
+
+        shares.push(secret);
+        let mut ret = Vec::with_capacity(n_shares);
+        for idx in share_indices().take(n_shares).skip(threshold - 1) {
+            ret.push(Codex32String::interpolate_at(&shares, idx)?);
+        }
+        // Put the random shares first, and drop the secret
+        shares.pop();
+        shares.extend(ret);
+        Ok(shares)
+    }
+}
+
+/// Returns a `fill` function, for use with `split_with`, which derives the
+/// payloads of the random shares using HMAC-SHA256 keyed with `key`.
+///
+/// See [`Codex32String::split_deterministic`] for the exact construction.
+pub(crate) fn deterministic_fill<'a>(
+    tag: &'a [u8],
+    hrp: &'a str,
+    key: &'a [u8],
+    extra_entropy: &'a [u8],
+) -> impl FnMut(&str, &mut [u8]) + 'a {
+    move |header, out| {
+        let mut msg =
+            Vec::with_capacity(tag.len() + hrp.len() + header.len() + extra_entropy.len() + 11);
+        msg.extend_from_slice(tag);
+        msg.push(0);
+        msg.extend(hrp.bytes().map(|b| b.to_ascii_lowercase()));
+        msg.push(0);
+        msg.extend(header.bytes().map(|b| b.to_ascii_lowercase()));
+        msg.extend_from_slice(&(extra_entropy.len() as u64).to_be_bytes());
+        msg.extend_from_slice(extra_entropy);
+
+        for (i, chunk) in out.chunks_mut(32).enumerate() {
+            let mut engine = HmacEngine::<sha256::Hash>::new(key);
+            engine.input(&msg);
+            engine.input(&[i as u8]);
+            let block = Hmac::<sha256::Hash>::from_engine(engine);
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        #[cfg(feature = "zeroize")]
+        msg.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn split_recover(
+            seed in prop_oneof![vec(any::<u8>(), 16..=44), vec(any::<u8>(), 63..=64)],
+            extra_entropy in vec(any::<u8>(), 0..8),
+            threshold in 2usize..=9,
+            extra_shares in 0usize..=22,
+            order in Just((0..31).collect::<Vec<usize>>()).prop_shuffle(),
+        ) {
+            let n_shares = threshold + extra_shares;
+            let shares = Codex32String::split_deterministic(
+                "ms", threshold, "leet", &seed, &extra_entropy, n_shares,
+            ).unwrap();
+            prop_assert_eq!(shares.len(), n_shares);
+
+            // Every generated string is valid
+            for share in &shares {
+                let parsed = Codex32String::from_string(share.expose_secret().into()).unwrap();
+                prop_assert_eq!(&parsed, share);
+            }
+
+            // Any `threshold` shares recover the seed, and every other share
+            let subset: Vec<Codex32String> = order
+                .iter()
+                .filter(|&&i| i < n_shares)
+                .take(threshold)
+                .map(|&i| shares[i].clone())
+                .collect();
+            let secret = Codex32String::from_seed("ms", threshold, "leet", Fe::S, &seed).unwrap();
+            prop_assert_eq!(Codex32String::interpolate_at(&subset, Fe::S).unwrap(), secret);
+            prop_assert_eq!(&Codex32String::interpolate_at(&subset, Fe::S).unwrap().parts().data()[..], &seed[..]);
+            for share in &shares {
+                let index = share.parts().share_index();
+                prop_assert_eq!(&Codex32String::interpolate_at(&subset, index).unwrap(), share);
+            }
+        }
+    }
+
+    #[test]
+    fn deterministic_split() {
+        #[rustfmt::skip]
+        let seed = [
+            0xff, 0xee, 0xdd, 0xcc, 0xbb, 0xaa, 0x99, 0x88,
+            0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11, 0x00,
+        ];
+        let shares = Codex32String::split_deterministic("ms", 3, "leet", &seed, &[], 5).unwrap();
+        let strs: Vec<&str> = shares.iter().map(Codex32String::expose_secret).collect();
+        assert_eq!(
+            strs,
+            vec![
+                "ms13leetafp05hec44nvyvamt5f6zpt4kg5qq0rm0p7ed3te",
+                "ms13leetcuxr2cfv3jav9pvgxternrwvauyn0htqdw7eg25d",
+                "ms13leetd22e59g289tvsjs4hts8zg0yx97qwqly5l05s60h",
+                "ms13leetedmuu5pgwl5v9rlruj2kwcfm33uz7r0p3qdh8da5",
+                "ms13leetfws2uxs6u0vv3ajdqdntw3d2pukzsvn727u66xe6",
+            ],
+        );
+
+        // Same inputs, same shares
+        let again = Codex32String::split_deterministic("ms", 3, "leet", &seed, &[], 5).unwrap();
+        assert_eq!(shares, again);
+        // Extra entropy changes the random shares
+        let extra = Codex32String::split_deterministic("ms", 3, "leet", &seed, b"dice", 5).unwrap();
+        assert_ne!(shares[0], extra[0]);
+
+        // Any three shares recover the seed
+        let secret = Codex32String::from_seed("ms", 3, "leet", Fe::S, &seed).unwrap();
+        for i in 0..5 {
+            for j in i + 1..5 {
+                for k in j + 1..5 {
+                    let subset = [shares[i].clone(), shares[j].clone(), shares[k].clone()];
+                    assert_eq!(
+                        Codex32String::interpolate_at(&subset, Fe::S).unwrap(),
+                        secret
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn deterministic_split_errors() {
+        let seed = [0; 16];
+        assert!(matches!(
+            Codex32String::split_deterministic("ms", 0, "leet", &seed, &[], 5),
+            Err(Error::InvalidThresholdN(0)),
+        ));
+        assert!(matches!(
+            Codex32String::split_deterministic("ms", 1, "leet", &seed, &[], 5),
+            Err(Error::InvalidThresholdN(1)),
+        ));
+        assert!(matches!(
+            Codex32String::split_deterministic("ms", 3, "leet", &seed, &[], 2),
+            Err(Error::ThresholdNotPassed {
+                threshold: 3,
+                n_shares: 2
+            }),
+        ));
+        assert!(matches!(
+            Codex32String::split_deterministic("ms", 3, "leet", &seed, &[], 32),
+            Err(Error::TooManyShares(32)),
+        ));
+        assert!(matches!(
+            Codex32String::split_deterministic("ms", 3, "lee", &seed, &[], 3),
+            Err(Error::IdNotLength4(3)),
+        ));
+    }
+}
@@ -0,0 +1,216 @@
+// Rust Codex32 Library and Reference Implementation
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Verification
+//!
+//! Checks that a set of shares are consistent with each other, i.e. that
+//! they all lie on a single polynomial of degree one less than the
+//! threshold, without recovering the master seed.
+//!
+//! Any `k` shares of a `k`-of-`n` set determine all the others, so given
+//! `k + 1` or more shares we can interpolate each share from `k` of the
+//! others, and compare. Only shares at the indices of the input shares are
+//! ever computed, never the `S` share.
+//!
+
+use super::{lagrange_coefficients, Codex32String, Error};
+use crate::field::Fe;
+use alloc::vec::Vec;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+impl Codex32String {
+    /// Checks that a set of at least `k + 1` shares, for threshold `k`, all
+    /// lie on one polynomial, and returns the indices of any inconsistent
+    /// shares.
+    ///
+    /// Returns an empty vector if the shares are consistent. Otherwise, finds
+    /// the largest set of more than `k` shares which are consistent with each
+    /// other, and returns the indices of the shares outside of it. If there is
+    /// no such set, or more than one, it is not possible to tell which shares
+    /// are at fault, and every index is returned.
+    ///
+    /// With `e` bad shares out of `n`, the bad shares are always identified if
+    /// `n >= k + 2e`, and usually (unless the bad shares themselves happen to
+    /// be consistent with some of the others) if `n >= k + e + 1`. This may
+    /// try every set of `k` shares, so can be slow for large sets of shares
+    /// with many bad ones.
+    pub fn verify_consistency(shares: &[Codex32String]) -> Result<Vec<Fe>, Error> {
+        let indices = Codex32String::interpolation_indices(shares)?;
+        // Check for repeated indices
+        lagrange_coefficients(&indices, Fe::S)?;
+        let threshold = shares[0].parts().threshold.max(1);
+        if shares.len() <= threshold {
+            return Err(Error::ThresholdNotPassed {
+                threshold: threshold + 1,
+                n_shares: shares.len(),
+            });
+        }
+
+        // Try the polynomial through each set of `threshold` shares in turn,
+        // keeping the largest set of shares which agree with one
+        let n = shares.len();
+        let mut best = Agreement::default();
+        let mut ambiguous = false;
+        let mut basis: Vec<usize> = (0..threshold).collect();
+        loop {
+            let agreement = agreement(shares, &basis);
+            if agreement.count() == n {
+                return Ok(vec![]);
+            }
+            if agreement.count() > best.count() {
+                best = agreement;
+                ambiguous = false;
+            } else if agreement.count() == best.count() && agreement != best {
+                ambiguous = true;
+            }
+            // A polynomial agreeing with `a` shares leaves at most `n - a`
+            // shares, along with `threshold - 1` of its own, for any other
+            // polynomial to agree with, so if that is fewer than `a`, no other
+            // polynomial can do as well
+            if 2 * best.count() > n + threshold - 1 || !next_subset(&mut basis, n) {
+                break;
+            }
+        }
+
+        if ambiguous || best.count() <= threshold {
+            return Ok(indices);
+        }
+        Ok(indices
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| !best.contains(*i))
+            .map(|(_, idx)| idx)
+            .collect())
+    }
+}
+
+/// A set of shares, by their position in a list of at most 32 shares, which
+/// is all there can be with distinct indices
+#[derive(Copy, Clone, PartialEq, Eq, Default)]
+struct Agreement(u32);
+
+impl Agreement {
+    fn insert(&mut self, i: usize) {
+        self.0 |= 1 << i;
+    }
+
+    fn contains(&self, i: usize) -> bool {
+        self.0 & (1 << i) != 0
+    }
+
+    fn count(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+}
+
+/// The set of shares which lie on the polynomial through the shares at the
+/// positions in `basis`
+fn agreement(shares: &[Codex32String], basis: &[usize]) -> Agreement {
+    let indices: Vec<Fe> = basis
+        .iter()
+        .map(|&i| shares[i].parts().share_index)
+        .collect();
+    let mut ret = Agreement::default();
+    for (i, share) in shares.iter().enumerate() {
+        if basis.contains(&i) {
+            ret.insert(i);
+            continue;
+        }
+        // unwrap ok since indices were checked to be distinct, and the
+        // target is not among them
+        let coeffs = lagrange_coefficients(&indices, share.parts().share_index).unwrap();
+        let mut expected: Vec<Fe> = share.data_fes().map(|_| Fe::Q).collect();
+        for (&b, coeff) in basis.iter().zip(coeffs) {
+            for (res, fe) in expected.iter_mut().zip(shares[b].data_fes()) {
+                *res += coeff.ct_mul(fe);
+            }
+        }
+        if expected.iter().copied().eq(share.data_fes()) {
+            ret.insert(i);
+        }
+        #[cfg(feature = "zeroize")]
+        expected.zeroize();
+    }
+    ret
+}
+
+/// Advances `subset`, a strictly increasing list of positions below `n`, to
+/// the next such list in lexicographic order, returning false if there is none
+fn next_subset(subset: &mut [usize], n: usize) -> bool {
+    let k = subset.len();
+    for i in (0..k).rev() {
+        if subset[i] < n - k + i {
+            subset[i] += 1;
+            for j in i + 1..k {
+                subset[j] = subset[j - 1] + 1;
+            }
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify() {
+        let seed = [0x42; 16];
+        let shares = Codex32String::split_deterministic("ms", 3, "leet", &seed, &[], 6).unwrap();
+        assert_eq!(
+            Codex32String::verify_consistency(&shares).unwrap(),
+            Vec::<Fe>::new()
+        );
+        assert_eq!(
+            Codex32String::verify_consistency(&shares[2..]).unwrap(),
+            Vec::<Fe>::new()
+        );
+
+        // A share from another split with the same header is detected
+        let other = Codex32String::split_deterministic("ms", 3, "leet", &seed, b"x", 6).unwrap();
+        let mut mixed = shares.clone();
+        mixed[4] = other[4].clone();
+        assert_eq!(
+            Codex32String::verify_consistency(&mixed).unwrap(),
+            vec![mixed[4].parts().share_index],
+        );
+        // Two bad shares out of six are identified, since the four good ones
+        // are more than the threshold
+        mixed[0] = other[0].clone();
+        assert_eq!(
+            Codex32String::verify_consistency(&mixed).unwrap(),
+            vec![mixed[0].parts().share_index, mixed[4].parts().share_index],
+        );
+        // But not three, since then no four shares are consistent
+        mixed[2] = other[2].clone();
+        assert_eq!(Codex32String::verify_consistency(&mixed).unwrap().len(), 6);
+        // With only k + 1 shares the bad one cannot be identified
+        assert_eq!(
+            Codex32String::verify_consistency(&mixed[1..5])
+                .unwrap()
+                .len(),
+            4
+        );
+
+        assert!(matches!(
+            Codex32String::verify_consistency(&shares[..3]),
+            Err(Error::ThresholdNotPassed {
+                threshold: 4,
+                n_shares: 3
+            }),
+        ));
+    }
+}
@@ -0,0 +1,349 @@
+// Rust Codex32 Library and Reference Implementation
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Volvelles
+//!
+//! Generates the paper computers ("volvelles") used for by-hand codex32
+//! computation, directly from the field arithmetic in this library, and
+//! renders them as SVG.
+//!
+//! Every volvelle is modelled as a stack of concentric rings of bech32
+//! characters, outermost ring first. The "table" volvelles (addition and
+//! fusion) have one ring of 32 selectors followed by one ring per possible
+//! second operand; the "log" volvelles (multiplication, translation and
+//! recovery) have rings of 31 characters listing successive powers of some
+//! generator, so that rotating one ring against another adds or subtracts
+//! discrete logarithms.
+//!
+
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use {
+    alloc::string::String,
+    core::{f64::consts::PI, fmt::Write},
+};
+
+use crate::field::Fe;
+
+/// Generator used to lay out the multiplication and translation wheels
+const MULT_LOGBASE: Fe = Fe::_5;
+/// Generator used to lay out the recovery wheel
+const RECOVERY_LOGBASE: Fe = Fe::_2;
+
+/// Width and height of the rendered SVG, in user units
+#[cfg(feature = "std")]
+const SVG_SIZE: f64 = 800.0;
+/// Radius of the outermost ring
+#[cfg(feature = "std")]
+const OUTER_RADIUS: f64 = 380.0;
+/// Radius below which no ring will be drawn
+#[cfg(feature = "std")]
+const INNER_RADIUS: f64 = 40.0;
+
+/// The different volvelles
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Kind {
+    /// Addition table, with selectors and operands in alphabetical order
+    Addition,
+    /// Multiplication wheel: two copies of the powers of `5`, both clockwise
+    Multiplication,
+    /// Translation wheel: the powers of `5` clockwise against the same powers
+    /// counterclockwise, which reads off quotients rather than products
+    Translation,
+    /// Addition table laid out in translation-wheel order, so that symbols
+    /// read off the translation wheel can be added without re-sorting
+    Fusion,
+    /// Recovery wheel: the powers of `2`, each offset by `S`
+    Recovery,
+}
+
+impl Kind {
+    /// All the volvelles, in the order they appear in the codex32 booklet
+    pub fn all() -> [Kind; 5] {
+        [
+            Kind::Addition,
+            Kind::Multiplication,
+            Kind::Translation,
+            Kind::Fusion,
+            Kind::Recovery,
+        ]
+    }
+
+    /// Short lowercase name, suitable for use as a filename
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Addition => "addition",
+            Kind::Multiplication => "multiplication",
+            Kind::Translation => "translation",
+            Kind::Fusion => "fusion",
+            Kind::Recovery => "recovery",
+        }
+    }
+}
+
+/// A volvelle, as a set of concentric rings of field elements
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Volvelle {
+    kind: Kind,
+    rings: Vec<Vec<Fe>>,
+}
+
+impl Volvelle {
+    /// Constructs the volvelle of the given kind
+    pub fn new(kind: Kind) -> Volvelle {
+        let rings = match kind {
+            Kind::Addition => ADDITION_TABLE.iter().map(|ring| ring.to_vec()).collect(),
+            Kind::Fusion => FUSION_TABLE.iter().map(|ring| ring.to_vec()).collect(),
+            Kind::Multiplication => {
+                vec![MULTIPLICATION_WHEEL.to_vec(), MULTIPLICATION_WHEEL.to_vec()]
+            }
+            Kind::Translation => vec![MULTIPLICATION_WHEEL.to_vec(), TRANSLATION_WHEEL.to_vec()],
+            Kind::Recovery => vec![RECOVERY_WHEEL.to_vec()],
+        };
+        Volvelle { kind, rings }
+    }
+
+    /// Which volvelle this is
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// The rings of the volvelle, outermost first, each listed clockwise
+    /// starting from the top
+    pub fn rings(&self) -> &[Vec<Fe>] {
+        &self.rings
+    }
+
+    /// Renders the volvelle as a standalone SVG document
+    ///
+    /// Requires the `std` feature, for trigonometry.
+    #[cfg(feature = "std")]
+    pub fn to_svg(&self) -> String {
+        let center = SVG_SIZE / 2.0;
+        let ring_width = (OUTER_RADIUS - INNER_RADIUS) / self.rings.len() as f64;
+        let ring_width = ring_width.min(60.0);
+        let font_size = (ring_width * 0.6).min(24.0);
+
+        let mut ret = String::new();
+        // Writing to a `String` cannot fail, so the `unwrap`s below are fine.
+        writeln!(
+            ret,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">",
+            SVG_SIZE,
+        )
+        .unwrap();
+        writeln!(ret, "<title>codex32 {} volvelle</title>", self.kind.name()).unwrap();
+        writeln!(
+            ret,
+            "<g font-family=\"monospace\" font-size=\"{:.2}\" text-anchor=\"middle\" dominant-baseline=\"central\">",
+            font_size,
+        )
+        .unwrap();
+        for (n, ring) in self.rings.iter().enumerate() {
+            let outer = OUTER_RADIUS - ring_width * n as f64;
+            let mid = outer - ring_width / 2.0;
+            writeln!(
+                ret,
+                "<circle cx=\"{0:.2}\" cy=\"{0:.2}\" r=\"{1:.2}\" fill=\"none\" stroke=\"black\"/>",
+                center, outer,
+            )
+            .unwrap();
+            for (i, fe) in ring.iter().enumerate() {
+                let angle = 360.0 * i as f64 / ring.len() as f64;
+                let theta = angle * PI / 180.0;
+                let x = center + mid * theta.sin();
+                let y = center - mid * theta.cos();
+                writeln!(
+                    ret,
+                    "<text x=\"{:.2}\" y=\"{:.2}\" transform=\"rotate({:.2} {:.2} {:.2})\">{}</text>",
+                    x,
+                    y,
+                    angle,
+                    x,
+                    y,
+                    fe.to_char().to_ascii_uppercase(),
+                )
+                .unwrap();
+            }
+        }
+        let innermost = OUTER_RADIUS - ring_width * self.rings.len() as f64;
+        writeln!(
+            ret,
+            "<circle cx=\"{0:.2}\" cy=\"{0:.2}\" r=\"{1:.2}\" fill=\"none\" stroke=\"black\"/>",
+            center, innermost,
+        )
+        .unwrap();
+        ret.push_str("</g>\n</svg>\n");
+        ret
+    }
+}
+
+/// The multiplication wheel: the 31 powers of `5`, clockwise from `P`
+pub const MULTIPLICATION_WHEEL: [Fe; 31] = log_wheel(MULT_LOGBASE, Fe::Q, false);
+
+/// The inner ring of the translation wheel: the 31 powers of `5`,
+/// counterclockwise from `P`
+pub const TRANSLATION_WHEEL: [Fe; 31] = log_wheel(MULT_LOGBASE, Fe::Q, true);
+
+/// The recovery wheel: the 31 powers of `2`, each offset by `S`
+pub const RECOVERY_WHEEL: [Fe; 31] = log_wheel(RECOVERY_LOGBASE, Fe::S, false);
+
+/// The addition table, with selectors and operands in alphabetical order
+///
+/// Ring `0` holds the selectors; ring `n + 1` holds the sum of each selector
+/// with the `n`th selector.
+pub const ADDITION_TABLE: [[Fe; 32]; 33] = table_rings(&Fe::ALPHABETICAL);
+
+/// The fusion table, laid out as [`ADDITION_TABLE`] but in translation-wheel
+/// order, i.e. `Q` followed by [`MULTIPLICATION_WHEEL`]
+pub const FUSION_TABLE: [[Fe; 32]; 33] = table_rings(&fusion_order());
+
+/// Lists the 31 powers of `logbase`, each added to `offset`.
+///
+/// If `reverse` is set, the powers are of the inverse of `logbase`, so that
+/// the resulting ring reads counterclockwise relative to the forward one.
+const fn log_wheel(logbase: Fe, offset: Fe, reverse: bool) -> [Fe; 31] {
+    let step = if reverse { logbase.ct_inv() } else { logbase };
+    let mut ret = [Fe::Q; 31];
+    let mut acc = Fe::P;
+    let mut i = 0;
+    while i < 31 {
+        ret[i] = acc.ct_add(offset);
+        acc = acc.ct_mul(step);
+        i += 1;
+    }
+    ret
+}
+
+/// The symbols in translation-wheel order: `Q` followed by the powers of `5`
+const fn fusion_order() -> [Fe; 32] {
+    let wheel = log_wheel(MULT_LOGBASE, Fe::Q, false);
+    let mut ret = [Fe::Q; 32];
+    let mut i = 0;
+    while i < 31 {
+        ret[i + 1] = wheel[i];
+        i += 1;
+    }
+    ret
+}
+
+/// Lays out an addition table with the given ordering of symbols.
+///
+/// The first ring holds the selectors; ring `n + 1` holds, under each
+/// selector, the sum of that selector with `order[n]`.
+const fn table_rings(order: &[Fe; 32]) -> [[Fe; 32]; 33] {
+    let mut ret = [[Fe::Q; 32]; 33];
+    ret[0] = *order;
+    let mut n = 0;
+    while n < 32 {
+        let mut i = 0;
+        while i < 32 {
+            ret[n + 1][i] = order[i].ct_add(order[n]);
+            i += 1;
+        }
+        n += 1;
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    fn ring_string(ring: &[Fe]) -> String {
+        ring.iter().copied().map(Fe::to_char).collect()
+    }
+
+    #[test]
+    fn log_wheels() {
+        // Same strings as in the `field` tests, which were checked against
+        // the physical wheels.
+        let mult = Volvelle::new(Kind::Multiplication);
+        assert_eq!(
+            ring_string(&mult.rings()[0]),
+            "p529kt3uw8hlmecvxr470na6djfsgyz"
+        );
+        assert_eq!(
+            ring_string(&mult.rings()[1]),
+            "p529kt3uw8hlmecvxr470na6djfsgyz"
+        );
+
+        let trans = Volvelle::new(Kind::Translation);
+        assert_eq!(
+            ring_string(&trans.rings()[0]),
+            "p529kt3uw8hlmecvxr470na6djfsgyz"
+        );
+        assert_eq!(
+            ring_string(&trans.rings()[1]),
+            "pzygsfjd6an074rxvcemlh8wu3tk925"
+        );
+
+        let recovery = Volvelle::new(Kind::Recovery);
+        assert_eq!(
+            ring_string(&recovery.rings()[0]),
+            "36xp78tgk9ldaecjy4mvh0funwr2zq5"
+        );
+    }
+
+    #[test]
+    fn const_tables() {
+        let mut acc = Fe::P;
+        for (fwd, rev) in MULTIPLICATION_WHEEL.iter().zip(TRANSLATION_WHEEL.iter()) {
+            assert_eq!(*fwd, acc);
+            assert_eq!(*rev, Fe::P / acc);
+            acc *= Fe::_5;
+        }
+        assert_eq!(acc, Fe::P);
+        assert_eq!(ADDITION_TABLE[0], Fe::ALPHABETICAL);
+        assert_eq!(FUSION_TABLE[0][0], Fe::Q);
+        assert_eq!(FUSION_TABLE[0][1..], MULTIPLICATION_WHEEL);
+    }
+
+    #[test]
+    fn addition_tables() {
+        for kind in &[Kind::Addition, Kind::Fusion] {
+            let volvelle = Volvelle::new(*kind);
+            let rings = volvelle.rings();
+            assert_eq!(rings.len(), 33);
+            // Every field element appears exactly once as a selector
+            let mut sorted = rings[0].clone();
+            sorted.sort_by_key(|fe| fe.to_u8());
+            assert_eq!(
+                sorted,
+                (0..32).map(|i| Fe::from_u8(i).unwrap()).collect::<Vec<_>>()
+            );
+            // and the table actually adds
+            for (n, addend) in rings[0].iter().enumerate() {
+                for (i, sel) in rings[0].iter().enumerate() {
+                    assert_eq!(rings[n + 1][i], *sel + *addend);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn svg_contains_every_symbol() {
+        for kind in &Kind::all() {
+            let volvelle = Volvelle::new(*kind);
+            let svg = volvelle.to_svg();
+            assert!(svg.starts_with("<svg "));
+            assert!(svg.ends_with("</svg>\n"));
+            let n_text = svg.matches("<text ").count();
+            let n_syms: usize = volvelle.rings().iter().map(Vec::len).sum();
+            assert_eq!(n_text, n_syms);
+        }
+    }
+}
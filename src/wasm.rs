@@ -0,0 +1,241 @@
+// Rust Codex32 Library and Reference Implementation
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! WebAssembly Bindings
+//!
+//! Exposes parsing, checksum verification, error correction, splitting and
+//! recovery to JavaScript through `wasm-bindgen`, which also generates the
+//! TypeScript declarations. Build with
+//!
+//! ```text
+//! cargo rustc --lib --release --target wasm32-unknown-unknown \
+//!     --features "wasm-bindgen zeroize" --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg \
+//!     target/wasm32-unknown-unknown/release/codex32.wasm
+//! ```
+//!
+//! Errors are thrown as JavaScript `Error`s, whose message is the `Debug`
+//! output of the corresponding [`Error`], which never includes secret data.
+//!
+//! Enabling the `zeroize` feature as well is recommended, so that strings
+//! are wiped from WebAssembly memory when their `free` method is called.
+//! Strings passed in from, or returned to, JavaScript are copies which this
+//! library cannot wipe.
+//!
+
+// The code generated by `wasm-bindgen` uses features newer than our MSRV.
+// This module is never built with the MSRV toolchain, since `wasm-bindgen`
+// itself needs a far newer compiler, so clippy's MSRV lint does not apply.
+#![allow(clippy::incompatible_msrv)]
+
+use alloc::{string::String, vec::Vec};
+use wasm_bindgen::prelude::*;
+
+use super::{check_string, Codex32String, Error};
+use crate::field::Fe;
+
+/// Converts a library error to a JavaScript `Error`
+fn js_error(e: Error) -> JsError {
+    JsError::new(&format!("{:?}", e))
+}
+
+/// A codex32 string, containing a valid checksum
+#[wasm_bindgen(js_name = Codex32String)]
+pub struct JsCodex32String(Codex32String);
+
+#[wasm_bindgen(js_class = Codex32String)]
+impl JsCodex32String {
+    /// Parses a codex32 string, checking its checksum.
+    pub fn parse(s: &str) -> Result<JsCodex32String, JsError> {
+        check_string(s).map_err(js_error)?;
+        Ok(JsCodex32String(Codex32String(s.into())))
+    }
+
+    /// Parses a codex32 string, correcting a single substituted character
+    /// if necessary.
+    ///
+    /// See [`Codex32String::from_string_corrected`].
+    pub fn correct(s: &str) -> Result<Correction, JsError> {
+        let (corrected, position) = Codex32String::from_str_corrected(s).map_err(js_error)?;
+        Ok(Correction {
+            string: corrected,
+            position,
+        })
+    }
+
+    /// Creates a codex32 string encoding `seed`, with the given header.
+    #[wasm_bindgen(js_name = fromSeed)]
+    pub fn from_seed(
+        hrp: &str,
+        threshold: usize,
+        id: &str,
+        share_index: &str,
+        seed: &[u8],
+    ) -> Result<JsCodex32String, JsError> {
+        let share_index: Fe = share_index.parse().map_err(js_error)?;
+        Codex32String::from_seed(hrp, threshold, id, share_index, seed)
+            .map(JsCodex32String)
+            .map_err(js_error)
+    }
+
+    /// The human-readable part
+    #[wasm_bindgen(getter)]
+    pub fn hrp(&self) -> String {
+        self.0.parts().hrp.into()
+    }
+
+    /// The threshold, with 0 meaning the string is an unshared secret
+    #[wasm_bindgen(getter)]
+    pub fn threshold(&self) -> usize {
+        self.0.parts().threshold
+    }
+
+    /// The four-character identifier
+    #[wasm_bindgen(getter)]
+    pub fn id(&self) -> String {
+        self.0.parts().id.into()
+    }
+
+    /// The share index, as a single lowercase character
+    #[wasm_bindgen(getter, js_name = shareIndex)]
+    pub fn share_index(&self) -> String {
+        self.0.parts().share_index.to_char().into()
+    }
+
+    /// The full string, including the (possibly secret) payload
+    #[wasm_bindgen(js_name = exposeSecret)]
+    pub fn expose_secret(&self) -> String {
+        self.0.expose_secret().into()
+    }
+
+    /// The payload as bytes, which for an `s` share is the master seed
+    pub fn data(&self) -> Vec<u8> {
+        self.0.parts().data().to_vec()
+    }
+}
+
+/// The result of correcting a codex32 string
+#[wasm_bindgen]
+pub struct Correction {
+    string: Codex32String,
+    position: Option<usize>,
+}
+
+#[wasm_bindgen]
+impl Correction {
+    /// The corrected string
+    #[wasm_bindgen(getter)]
+    pub fn string(&self) -> JsCodex32String {
+        JsCodex32String(self.string.clone())
+    }
+
+    /// The position of the corrected character, or `undefined` if the
+    /// string was already valid
+    #[wasm_bindgen(getter)]
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+}
+
+/// Checks whether a string is a valid codex32 string, with a valid checksum.
+#[wasm_bindgen(js_name = verifyChecksum)]
+pub fn verify_checksum(s: &str) -> bool {
+    check_string(s).is_ok()
+}
+
+/// Splits a master seed into `nShares` shares, deterministically.
+///
+/// See [`Codex32String::split_deterministic`].
+#[wasm_bindgen]
+pub fn split(
+    hrp: &str,
+    threshold: usize,
+    id: &str,
+    seed: &[u8],
+    extra_entropy: &[u8],
+    n_shares: usize,
+) -> Result<Vec<JsCodex32String>, JsError> {
+    let shares =
+        Codex32String::split_deterministic(hrp, threshold, id, seed, extra_entropy, n_shares)
+            .map_err(js_error)?;
+    Ok(shares.into_iter().map(JsCodex32String).collect())
+}
+
+/// Parses an array of shares
+fn parse_shares(shares: Vec<String>) -> Result<Vec<Codex32String>, JsError> {
+    shares
+        .into_iter()
+        .map(Codex32String::from_string)
+        .collect::<Result<_, _>>()
+        .map_err(js_error)
+}
+
+/// Interpolates an array of shares at the index `target`, a single bech32
+/// character.
+///
+/// Using the target `s` recovers the master seed as a codex32 string.
+#[wasm_bindgen]
+pub fn interpolate(shares: Vec<String>, target: &str) -> Result<JsCodex32String, JsError> {
+    let shares = parse_shares(shares)?;
+    let target: Fe = target.parse().map_err(js_error)?;
+    Codex32String::interpolate_at(&shares, target)
+        .map(JsCodex32String)
+        .map_err(js_error)
+}
+
+/// Recovers the master seed, as bytes, from an array of shares.
+#[wasm_bindgen]
+pub fn recover(shares: Vec<String>) -> Result<Vec<u8>, JsError> {
+    let shares = parse_shares(shares)?;
+    let secret = Codex32String::interpolate_at(&shares, Fe::S).map_err(js_error)?;
+    Ok(secret.parts().data().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    // Errors can only be constructed on wasm32, so only the success paths
+    // are tested here.
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let seed = [0x42; 16];
+        let shares = split("ms", 2, "leet", &seed, &[], 3).unwrap();
+        let strings: Vec<String> = shares.iter().map(|s| s.expose_secret()).collect();
+        assert!(strings.iter().all(|s| verify_checksum(s)));
+        assert_eq!(shares[1].threshold(), 2);
+        assert_eq!(shares[1].id(), "leet");
+        assert_eq!(shares[1].share_index(), "c");
+        assert_eq!(recover(strings[1..].to_vec()).unwrap(), seed);
+
+        let secret = interpolate(strings[..2].to_vec(), "s").unwrap();
+        let from_seed = JsCodex32String::from_seed("ms", 2, "leet", "s", &seed).unwrap();
+        assert_eq!(secret.expose_secret(), from_seed.expose_secret());
+        assert_eq!(secret.data(), seed);
+    }
+
+    #[test]
+    fn correct() {
+        let valid = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw";
+        let parsed = JsCodex32String::parse(valid).unwrap();
+        assert_eq!(parsed.hrp(), "ms");
+        assert_eq!(parsed.share_index(), "s");
+
+        let typo = valid.replace("4nzv", "4nqv");
+        assert!(!verify_checksum(&typo));
+        let correction = JsCodex32String::correct(&typo).unwrap();
+        assert_eq!(correction.string().expose_secret(), valid);
+        assert_eq!(correction.position(), Some(37));
+    }
+}
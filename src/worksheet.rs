@@ -0,0 +1,516 @@
+// Rust Codex32 Library and Reference Implementation
+// Written in 2023 by
+//   Andrew Poelstra <apoelstra@wpsoftware.net>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Worksheets
+//!
+//! Produces filled-in copies of the codex32 worksheets, so that somebody
+//! working by hand can compare their work against the correct computation
+//! and find the first place where they went wrong.
+//!
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Write};
+
+use super::{checksum, Codex32String, Error};
+use crate::field::Fe;
+
+/// A single row of the checksum worksheet
+///
+/// Each row corresponds to one call to [`checksum::Engine::input_fe`]: the
+/// residue is shifted up by one place, the new symbol is written in the
+/// lowest place, and the symbol that was shifted out of the top selects a
+/// multiple of the generator which is added to the result.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct ChecksumStep {
+    input: Fe,
+    leading: Fe,
+    shifted: Vec<Fe>,
+    addend: Vec<Fe>,
+    residue: Vec<Fe>,
+}
+
+impl fmt::Debug for ChecksumStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ChecksumStep(<redacted>)")
+    }
+}
+
+impl ChecksumStep {
+    /// The symbol of the share which was fed in on this row
+    pub fn input(&self) -> Fe {
+        self.input
+    }
+
+    /// The symbol shifted out of the top of the residue, which selects the
+    /// row of the generator table to add
+    pub fn leading(&self) -> Fe {
+        self.leading
+    }
+
+    /// The previous residue, shifted up one place, with the input symbol
+    /// appended
+    pub fn shifted(&self) -> &[Fe] {
+        &self.shifted
+    }
+
+    /// The multiple of the generator which is added to the shifted residue
+    pub fn addend(&self) -> &[Fe] {
+        &self.addend
+    }
+
+    /// The residue after this row
+    pub fn residue(&self) -> &[Fe] {
+        &self.residue
+    }
+}
+
+/// A complete trace of the checksum worksheet for a single share
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct ChecksumWorksheet {
+    share: Codex32String,
+    initial: Vec<Fe>,
+    steps: Vec<ChecksumStep>,
+    target: Vec<Fe>,
+}
+
+impl fmt::Debug for ChecksumWorksheet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ChecksumWorksheet")
+            .field("share", &self.share)
+            .field("steps", &self.steps.len())
+            .finish()
+    }
+}
+
+impl ChecksumWorksheet {
+    /// Computes the checksum worksheet for a codex32 string.
+    ///
+    /// The HRP is not part of the worksheet; instead its contribution is
+    /// folded into the initial residue, which is what the printed worksheets
+    /// have pre-filled for the `ms` prefix.
+    pub fn new(s: &Codex32String) -> ChecksumWorksheet {
+        let parts = s.parts();
+        let mut engine = if s.0.len() > 93 {
+            checksum::Engine::new_codex32_long()
+        } else {
+            checksum::Engine::new_codex32_short()
+        };
+        // unwrap OK since the string was validated on parse
+        engine.input_hrp(parts.hrp).unwrap();
+        let initial = engine.residue().to_vec();
+
+        let data = &s.0[parts.hrp.len() + 1..];
+        let mut steps = Vec::with_capacity(data.len());
+        for ch in data.chars() {
+            let input = Fe::from_char(ch).unwrap();
+            let before = engine.residue();
+            let leading = before[0];
+            let mut shifted = before[1..].to_vec();
+            shifted.push(input);
+
+            engine.input_fe(input);
+            let residue = engine.residue().to_vec();
+            let addend = residue
+                .iter()
+                .zip(shifted.iter())
+                .map(|(r, s)| *r - *s)
+                .collect();
+            steps.push(ChecksumStep {
+                input,
+                leading,
+                shifted,
+                addend,
+                residue,
+            });
+        }
+
+        ChecksumWorksheet {
+            share: s.clone(),
+            initial,
+            target: engine.target().to_vec(),
+            steps,
+        }
+    }
+
+    /// The residue before any data symbols are entered
+    pub fn initial_residue(&self) -> &[Fe] {
+        &self.initial
+    }
+
+    /// Each row of the worksheet, in order
+    pub fn steps(&self) -> &[ChecksumStep] {
+        &self.steps
+    }
+
+    /// The residue after the last row; for a valid share this is the target
+    /// residue `SECRETSHARE32` (or `SECRETSHARE32EX` for long strings)
+    pub fn final_residue(&self) -> &[Fe] {
+        self.steps
+            .last()
+            .map(|s| &s.residue[..])
+            .unwrap_or(&self.initial)
+    }
+
+    /// Compares a sequence of by-hand residues against the worksheet, and
+    /// returns the index of the first row which differs.
+    ///
+    /// If the user stopped early, and all the rows they filled in are correct,
+    /// returns `None`.
+    pub fn first_mismatch(&self, residues: &[Vec<Fe>]) -> Option<usize> {
+        self.steps
+            .iter()
+            .zip(residues.iter())
+            .position(|(step, res)| step.residue != *res)
+    }
+
+    /// Renders the worksheet as a standalone, printable HTML page
+    pub fn to_html(&self) -> String {
+        let title = format!("Checksum worksheet for {}", header(&self.share));
+        let mut ret = html_header(&title);
+        ret.push_str("<table>\n");
+        html_row(&mut ret, "residue", "start", &self.initial);
+        for (n, step) in self.steps.iter().enumerate() {
+            let label = format!("{} {}", n + 1, step.input.to_char().to_ascii_uppercase());
+            html_row(&mut ret, "shifted", &label, &step.shifted);
+            let label = format!("+{}", step.leading.to_char().to_ascii_uppercase());
+            html_row(&mut ret, "addend", &label, &step.addend);
+            html_row(&mut ret, "residue", "=", &step.residue);
+        }
+        html_row(&mut ret, "target", "target", &self.target);
+        ret.push_str("</table>\n");
+        ret.push_str(HTML_FOOTER);
+        ret
+    }
+}
+
+/// A single share's row of the translation worksheet
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct TranslatedShare {
+    index: Fe,
+    multiplier: Fe,
+    original: Vec<Fe>,
+    translated: Vec<Fe>,
+}
+
+impl fmt::Debug for TranslatedShare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TranslatedShare")
+            .field("index", &self.index)
+            .field("multiplier", &self.multiplier)
+            .field("payload", &"<redacted>")
+            .finish()
+    }
+}
+
+impl TranslatedShare {
+    /// The share index of the input share
+    pub fn index(&self) -> Fe {
+        self.index
+    }
+
+    /// The symbol by which every character of the share is multiplied
+    pub fn multiplier(&self) -> Fe {
+        self.multiplier
+    }
+
+    /// The data part of the input share (everything after the separator)
+    pub fn original(&self) -> &[Fe] {
+        &self.original
+    }
+
+    /// The data part of the input share, multiplied by the multiplier
+    pub fn translated(&self) -> &[Fe] {
+        &self.translated
+    }
+}
+
+/// A complete trace of the translation and fusion worksheets used to derive
+/// a new share (or recover the secret) from a set of existing shares
+///
+/// Each input share is "translated" by multiplying it by its Lagrange
+/// coefficient for the target index; the translated shares are then "fused"
+/// by adding them together to obtain the target share.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct TranslationWorksheet {
+    target: Fe,
+    shares: Vec<TranslatedShare>,
+    result: Codex32String,
+}
+
+impl fmt::Debug for TranslationWorksheet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TranslationWorksheet")
+            .field("target", &self.target)
+            .field("shares", &self.shares)
+            .field("result", &self.result)
+            .finish()
+    }
+}
+
+impl TranslationWorksheet {
+    /// Computes the translation worksheet for deriving the share at `target`
+    /// from a set of shares.
+    ///
+    /// Fails in the same cases that [`Codex32String::interpolate_at`] does.
+    pub fn new(shares: &[Codex32String], target: Fe) -> Result<TranslationWorksheet, Error> {
+        let indices = Codex32String::interpolation_indices(shares)?;
+        let coeffs = super::lagrange_coefficients(&indices, target)?;
+
+        let mut result = vec![];
+        let mut translated_shares = Vec::with_capacity(shares.len());
+        for ((share, index), multiplier) in shares.iter().zip(indices).zip(coeffs) {
+            let original: Vec<Fe> = share.data_fes().collect();
+            let translated: Vec<Fe> = original.iter().map(|fe| fe.ct_mul(multiplier)).collect();
+            result.resize(translated.len(), Fe::Q);
+            for (res, fe) in result.iter_mut().zip(translated.iter()) {
+                *res += *fe;
+            }
+            translated_shares.push(TranslatedShare {
+                index,
+                multiplier,
+                original,
+                translated,
+            });
+        }
+
+        Ok(TranslationWorksheet {
+            target,
+            shares: translated_shares,
+            result: Codex32String::from_hrp_and_data(shares[0].parts().hrp, result),
+        })
+    }
+
+    /// The index of the share being derived
+    pub fn target(&self) -> Fe {
+        self.target
+    }
+
+    /// The translation of each input share, in the order they were given
+    pub fn shares(&self) -> &[TranslatedShare] {
+        &self.shares
+    }
+
+    /// The derived share, i.e. the sum of all the translated shares
+    pub fn result(&self) -> &Codex32String {
+        &self.result
+    }
+
+    /// Renders the worksheet as a standalone, printable HTML page
+    pub fn to_html(&self) -> String {
+        let target = self.target.to_char().to_ascii_uppercase();
+        let mut ret = html_header(&format!("Translation worksheet for share {}", target));
+        ret.push_str("<table>\n");
+        for share in &self.shares {
+            let label = share.index.to_char().to_ascii_uppercase();
+            html_row(&mut ret, "shifted", &label.to_string(), &share.original);
+            let label = format!("&times;{}", share.multiplier.to_char().to_ascii_uppercase());
+            html_row(&mut ret, "addend", &label, &share.translated);
+        }
+        let result: Vec<Fe> = self.result.data_fes().collect();
+        html_row(&mut ret, "residue", &target.to_string(), &result);
+        ret.push_str("</table>\n");
+        ret.push_str(HTML_FOOTER);
+        ret
+    }
+}
+
+/// The header of a share (everything up to and including the share index),
+/// which is all of it that may appear in a page title
+fn header(s: &Codex32String) -> &str {
+    // HRP, separator, threshold, four-character identifier and share index
+    &s.0[..s.parts().hrp.len() + 7]
+}
+
+/// Closing tags for every worksheet HTML page
+const HTML_FOOTER: &str = "</body>\n</html>\n";
+
+/// Opening tags, including a stylesheet and heading, for a worksheet HTML page
+fn html_header(title: &str) -> String {
+    let mut ret = String::new();
+    // Writing to a `String` cannot fail, so the `unwrap`s below are fine.
+    ret.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    ret.push_str("<title>codex32: ");
+    push_escaped(&mut ret, title);
+    ret.push_str("</title>\n");
+    ret.push_str(
+        "<style>\n\
+         body { font-family: monospace; }\n\
+         table { border-collapse: collapse; }\n\
+         td, th { border: 1px solid #888; padding: 0 0.3em; text-align: center; }\n\
+         tr.addend td { color: #a00; }\n\
+         tr.residue td { font-weight: bold; }\n\
+         </style>\n</head>\n<body>\n",
+    );
+    ret.push_str("<h1>");
+    push_escaped(&mut ret, title);
+    ret.push_str("</h1>\n");
+    ret
+}
+
+/// Appends text to an HTML page, escaping the characters which are special
+/// in HTML, since an HRP may contain any printable ASCII
+fn push_escaped(ret: &mut String, s: &str) {
+    for ch in s.chars() {
+        match ch {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\'' => ret.push_str("&#39;"),
+            _ => ret.push(ch),
+        }
+    }
+}
+
+/// Writes a single row of field elements into an HTML table
+fn html_row(ret: &mut String, cls: &str, label: &str, fes: &[Fe]) {
+    write!(ret, "<tr class=\"{}\"><th>{}</th>", cls, label).unwrap();
+    for fe in fes {
+        write!(ret, "<td>{}</td>", fe.to_char().to_ascii_uppercase()).unwrap();
+    }
+    ret.push_str("</tr>\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Codex32Buf;
+
+    fn fe_string(fes: &[Fe]) -> String {
+        fes.iter().copied().map(Fe::to_char).collect()
+    }
+
+    #[test]
+    fn checksum_worksheet() {
+        let share =
+            Codex32String::from_string("ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw".into())
+                .unwrap();
+        let sheet = ChecksumWorksheet::new(&share);
+        assert_eq!(sheet.steps().len(), 45);
+        assert_eq!(fe_string(sheet.final_residue()), "secretshare32");
+
+        // Every row must be a shift plus an addend
+        let mut prev = sheet.initial_residue().to_vec();
+        for step in sheet.steps() {
+            assert_eq!(step.leading(), prev[0]);
+            assert_eq!(&step.shifted()[..12], &prev[1..]);
+            assert_eq!(step.shifted()[12], step.input());
+            for i in 0..13 {
+                assert_eq!(step.residue()[i], step.shifted()[i] + step.addend()[i]);
+            }
+            prev = step.residue().to_vec();
+        }
+
+        // Find a mistake in a by-hand computation
+        let mut hand: Vec<Vec<Fe>> = sheet.steps().iter().map(|s| s.residue().to_vec()).collect();
+        assert_eq!(sheet.first_mismatch(&hand), None);
+        assert_eq!(sheet.first_mismatch(&hand[..10]), None);
+        hand[17][3] += Fe::P;
+        assert_eq!(sheet.first_mismatch(&hand), Some(17));
+
+        let html = sheet.to_html();
+        assert!(html.contains("<title>codex32: Checksum worksheet for ms10tests</title>"));
+        assert!(!html.contains("ms10testsxxxx"));
+        assert_eq!(html.matches("<tr class=\"addend\">").count(), 45);
+    }
+
+    #[test]
+    fn html_escaping() {
+        // An HRP may contain any printable ASCII, so must be escaped
+        let buf = Codex32Buf::from_seed("<B>&\"'", 0, "TEST", Fe::S, &[0; 16]).unwrap();
+        let html = ChecksumWorksheet::new(&Codex32String::from(&buf)).to_html();
+        assert!(html.contains(
+            "<title>codex32: Checksum worksheet for &lt;B&gt;&amp;&quot;&#39;10TESTS</title>"
+        ));
+        assert!(html.contains("<h1>Checksum worksheet for &lt;B&gt;&amp;&quot;&#39;10TESTS</h1>"));
+        assert!(!html.contains("<B>"));
+    }
+
+    #[test]
+    fn checksum_worksheet_long() {
+        let share = Codex32String::from_string(
+            "MS100C8VSM32ZXFGUHPCHTLUPZRY9X8GF2TVDW0S3JN54KHCE6MUA7LQPZYGSFJD6AN074RXVCEMLH8WU3TK925ACDEFGHJKLMNPQRSTUVWXY06FHPV80UNDVARHRAK".into()
+        ).unwrap();
+        let sheet = ChecksumWorksheet::new(&share);
+        assert_eq!(fe_string(sheet.final_residue()), "secretshare32ex");
+    }
+
+    #[test]
+    fn translation_worksheet() {
+        let shares = [
+            Codex32String::from_string("ms13casha320zyxwvutsrqpnmlkjhgfedca2a8d0zehn8a0t".into())
+                .unwrap(),
+            Codex32String::from_string("ms13cashcacdefghjklmnpqrstuvwxyz023949xq35my48dr".into())
+                .unwrap(),
+            Codex32String::from_string("ms13cashd0wsedstcdcts64cd7wvy4m90lm28w4ffupqs7rm".into())
+                .unwrap(),
+        ];
+        let sheet = TranslationWorksheet::new(&shares, Fe::S).unwrap();
+        assert_eq!(
+            sheet.result().expose_secret(),
+            "ms13cashsllhdmn9m42vcsamx24zrxgs3qqjzqud4m0d6nln",
+        );
+        assert_eq!(sheet.target(), Fe::S);
+        assert_eq!(sheet.shares().len(), 3);
+        // The multipliers of a Lagrange interpolation always sum to 1
+        let sum = sheet
+            .shares()
+            .iter()
+            .fold(Fe::Q, |acc, share| acc + share.multiplier());
+        assert_eq!(sum, Fe::P);
+        for (share, translated) in shares.iter().zip(sheet.shares()) {
+            assert_eq!(translated.index(), share.parts().share_index);
+            for (orig, tr) in translated.original().iter().zip(translated.translated()) {
+                assert_eq!(*orig * translated.multiplier(), *tr);
+            }
+        }
+
+        let html = sheet.to_html();
+        assert_eq!(html.matches("<tr class=\"addend\">").count(), 3);
+
+        // Debug output shows the headers but none of the payloads
+        let debug = format!("{:?}", sheet);
+        assert!(debug.contains("<redacted>"));
+        assert!(!debug.contains("320zyxw"));
+        assert!(!debug.contains("sllhdmn"));
+
+        // An upper-case HRP with a digit in it gives an upper-case result
+        let upper = [
+            Codex32String::from_string("MS212LEETAZYG3ZYG3ZYG3ZYG3ZYG3ZYG3ZYVZU0MGZW35HGA".into())
+                .unwrap(),
+            Codex32String::from_string("MS212LEETCYG3ZYG3ZYG3ZYG3ZYG3ZYG3ZYGHCH5Y4RNGDVFE".into())
+                .unwrap(),
+        ];
+        let sheet = TranslationWorksheet::new(&upper, Fe::S).unwrap();
+        assert_eq!(
+            sheet.result(),
+            &Codex32String::interpolate_at(&upper, Fe::S).unwrap()
+        );
+        assert!(Codex32String::from_string(sheet.result().expose_secret().into()).is_ok());
+
+        // Deriving an existing share is trivial
+        let sheet = TranslationWorksheet::new(&shares, Fe::C).unwrap();
+        assert_eq!(sheet.result(), &shares[1]);
+
+        // Errors are the same as for interpolation
+        let repeated = [shares[0].clone(), shares[0].clone(), shares[1].clone()];
+        assert!(matches!(
+            TranslationWorksheet::new(&repeated, Fe::S),
+            Err(Error::RepeatedIndex(Fe::A)),
+        ));
+    }
+}
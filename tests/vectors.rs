@@ -0,0 +1,512 @@
+// Rust Codex32 Library and Reference Implementation
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the CC0 Public Domain Dedication
+// along with this software.
+// If not, see <http://creativecommons.org/publicdomain/zero/1.0/>.
+//
+
+//! Test Vector Files
+//!
+//! Runs every `.json` and `.toml` file in `tests/data`, along with any files
+//! listed, separated by `:`, in the `CODEX32_VECTORS` environment variable,
+//! so that vectors from other implementations can be checked without
+//! touching any Rust code. All four sections are optional, as are all
+//! fields other than those needed to construct the input.
+//!
+//! ```toml
+//! [[valid]]
+//! string = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxx4nzvca9cmczlw"
+//! hrp = "ms"
+//! threshold = 0
+//! id = "test"
+//! share_index = "s"
+//! data = "318c6318c6318c6318c6318c6318c631"  # hex
+//! xprv = "xprv9s21ZrQH..."                     # BIP-32 master key of `data`
+//!
+//! [[invalid]]
+//! string = "ms10testsxxxxxxxxxxxxxxxxxxxxxxxxxxxxmazxdp4sx5q"
+//! error = "InvalidChecksum"                   # name of the `Error` variant
+//!
+//! [[interpolations]]
+//! shares = ["MS12NAMEA320...", "MS12NAMECACD..."]
+//! derived = { d = "MS12NAMEDLL4...", s = "MS12NAMES6XQ..." }
+//!
+//! [[seeds]]
+//! seed = "ffeeddccbbaa99887766554433221100"    # hex
+//! hrp = "ms"
+//! threshold = 3
+//! id = "cash"
+//! share_index = "s"
+//! string = "ms13cashsllhdmn9m42vcsamx24zrxgs3qqjzqud4m0d6nln"
+//! xprv = "xprv9s21ZrQH..."
+//! ```
+//!
+//! Every entry may also have a `comment`, which is included in any failure.
+//!
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{env, fmt, fs};
+
+use bitcoin_hashes::{sha256d, sha512, Hash, HashEngine, Hmac, HmacEngine};
+use codex32::{Codex32String, Error, Fe};
+use serde_json::{Map, Value};
+
+struct VectorFile {
+    valid: Vec<Valid>,
+    invalid: Vec<Invalid>,
+    interpolations: Vec<Interpolation>,
+    seeds: Vec<Seed>,
+}
+
+/// A valid string, and optionally what it should decode to
+struct Valid {
+    comment: Option<String>,
+    string: String,
+    hrp: Option<String>,
+    threshold: Option<usize>,
+    id: Option<String>,
+    share_index: Option<String>,
+    data: Option<String>,
+    xprv: Option<String>,
+}
+
+/// An invalid string, and optionally the error it should be rejected with
+struct Invalid {
+    comment: Option<String>,
+    string: String,
+    error: Option<String>,
+}
+
+/// A set of shares, and the shares which should be derived from them,
+/// keyed by share index
+struct Interpolation {
+    comment: Option<String>,
+    shares: Vec<String>,
+    derived: BTreeMap<String, String>,
+}
+
+/// A seed, and the string it should be encoded as
+struct Seed {
+    comment: Option<String>,
+    seed: String,
+    hrp: String,
+    threshold: usize,
+    id: String,
+    share_index: String,
+    string: String,
+    xprv: Option<String>,
+}
+
+/// A table from a vector file, whose fields are taken one at a time so that
+/// any which are left over can be rejected as unknown
+struct Entry(Map<String, Value>);
+
+impl Entry {
+    fn new(value: Value) -> Result<Entry, String> {
+        match value {
+            Value::Object(map) => Ok(Entry(map)),
+            other => Err(format!("expected a table, got {}", other)),
+        }
+    }
+
+    fn take(&mut self, key: &str) -> Option<Value> {
+        self.0.remove(key)
+    }
+
+    fn string(&mut self, key: &str) -> Result<Option<String>, String> {
+        match self.take(key) {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s)),
+            Some(other) => Err(format!("{}: expected a string, got {}", key, other)),
+        }
+    }
+
+    fn usize(&mut self, key: &str) -> Result<Option<usize>, String> {
+        match self.take(key) {
+            None => Ok(None),
+            Some(Value::Number(n)) => match n.as_u64() {
+                Some(n) => Ok(Some(n as usize)),
+                None => Err(format!("{}: expected a count, got {}", key, n)),
+            },
+            Some(other) => Err(format!("{}: expected a number, got {}", key, other)),
+        }
+    }
+
+    fn strings(&mut self, key: &str) -> Result<Vec<String>, String> {
+        match self.take(key) {
+            None => Ok(vec![]),
+            Some(Value::Array(values)) => values
+                .into_iter()
+                .map(|v| match v {
+                    Value::String(s) => Ok(s),
+                    other => Err(format!("{}: expected a string, got {}", key, other)),
+                })
+                .collect(),
+            Some(other) => Err(format!("{}: expected a list, got {}", key, other)),
+        }
+    }
+
+    fn string_map(&mut self, key: &str) -> Result<BTreeMap<String, String>, String> {
+        match self.take(key) {
+            None => Ok(BTreeMap::new()),
+            Some(Value::Object(map)) => map
+                .into_iter()
+                .map(|(k, v)| match v {
+                    Value::String(s) => Ok((k, s)),
+                    other => Err(format!("{}.{}: expected a string, got {}", key, k, other)),
+                })
+                .collect(),
+            Some(other) => Err(format!("{}: expected a table, got {}", key, other)),
+        }
+    }
+
+    fn entries<T, F>(&mut self, key: &str, from_entry: F) -> Result<Vec<T>, String>
+    where
+        F: Fn(Entry) -> Result<T, String>,
+    {
+        match self.take(key) {
+            None => Ok(vec![]),
+            Some(Value::Array(values)) => values
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| {
+                    Entry::new(v)
+                        .and_then(&from_entry)
+                        .map_err(|e| format!("{}[{}]: {}", key, i, e))
+                })
+                .collect(),
+            Some(other) => Err(format!("{}: expected a list, got {}", key, other)),
+        }
+    }
+
+    fn required<T>(key: &str, value: Option<T>) -> Result<T, String> {
+        value.ok_or_else(|| format!("missing field {}", key))
+    }
+
+    /// Fails if there are any fields which were not taken
+    fn finish(self) -> Result<(), String> {
+        match self.0.keys().next() {
+            Some(key) => Err(format!("unknown field {}", key)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Valid {
+    fn from_entry(mut e: Entry) -> Result<Valid, String> {
+        let ret = Valid {
+            comment: e.string("comment")?,
+            string: Entry::required("string", e.string("string")?)?,
+            hrp: e.string("hrp")?,
+            threshold: e.usize("threshold")?,
+            id: e.string("id")?,
+            share_index: e.string("share_index")?,
+            data: e.string("data")?,
+            xprv: e.string("xprv")?,
+        };
+        e.finish()?;
+        Ok(ret)
+    }
+}
+
+impl Invalid {
+    fn from_entry(mut e: Entry) -> Result<Invalid, String> {
+        let ret = Invalid {
+            comment: e.string("comment")?,
+            string: Entry::required("string", e.string("string")?)?,
+            error: e.string("error")?,
+        };
+        e.finish()?;
+        Ok(ret)
+    }
+}
+
+impl Interpolation {
+    fn from_entry(mut e: Entry) -> Result<Interpolation, String> {
+        let ret = Interpolation {
+            comment: e.string("comment")?,
+            shares: e.strings("shares")?,
+            derived: e.string_map("derived")?,
+        };
+        e.finish()?;
+        Ok(ret)
+    }
+}
+
+impl Seed {
+    fn from_entry(mut e: Entry) -> Result<Seed, String> {
+        let ret = Seed {
+            comment: e.string("comment")?,
+            seed: Entry::required("seed", e.string("seed")?)?,
+            hrp: Entry::required("hrp", e.string("hrp")?)?,
+            threshold: Entry::required("threshold", e.usize("threshold")?)?,
+            id: Entry::required("id", e.string("id")?)?,
+            share_index: Entry::required("share_index", e.string("share_index")?)?,
+            string: Entry::required("string", e.string("string")?)?,
+            xprv: e.string("xprv")?,
+        };
+        e.finish()?;
+        Ok(ret)
+    }
+}
+
+impl VectorFile {
+    fn from_value(value: Value) -> Result<VectorFile, String> {
+        let mut file = Entry::new(value)?;
+        let ret = VectorFile {
+            valid: file.entries("valid", Valid::from_entry)?,
+            invalid: file.entries("invalid", Invalid::from_entry)?,
+            interpolations: file.entries("interpolations", Interpolation::from_entry)?,
+            seeds: file.entries("seeds", Seed::from_entry)?,
+        };
+        file.finish()?;
+        Ok(ret)
+    }
+}
+
+/// Fails a single vector, with a message
+macro_rules! ensure {
+    ($cond:expr, $($arg:tt)*) => {
+        if !$cond {
+            return Err(format!($($arg)*));
+        }
+    };
+}
+
+/// Checks that an actual value, if expected, matches the expected value
+fn check<T: PartialEq + fmt::Debug>(
+    what: &str,
+    expected: Option<T>,
+    actual: T,
+) -> Result<(), String> {
+    match expected {
+        Some(expected) if expected != actual => Err(format!(
+            "{}: expected {:?}, got {:?}",
+            what, expected, actual
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Result<Vec<u8>, String> {
+    ensure!(s.len() % 2 == 0, "odd-length hex string {}", s);
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|e| format!("bad hex {}: {}", s, e)))
+        .collect()
+}
+
+fn parse_fe(s: &str) -> Result<Fe, String> {
+    s.parse()
+        .map_err(|e| format!("bad share index {}: {:?}", s, e))
+}
+
+/// The name of an error variant, which is how errors are given in vector files
+fn error_name(e: &Error) -> String {
+    format!("{:?}", e)
+        .chars()
+        .take_while(|c| c.is_alphanumeric())
+        .collect()
+}
+
+fn base58check(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    let mut data = data.to_vec();
+    data.extend_from_slice(&sha256d::Hash::hash(&data)[..4]);
+
+    // Repeatedly divide the big-endian number by 58
+    let mut digits = vec![];
+    let mut num = data.clone();
+    while num.iter().any(|&b| b != 0) {
+        let mut rem = 0u32;
+        for byte in num.iter_mut() {
+            let acc = (rem << 8) | u32::from(*byte);
+            *byte = (acc / 58) as u8;
+            rem = acc % 58;
+        }
+        digits.push(ALPHABET[rem as usize]);
+    }
+    digits.extend(data.iter().take_while(|&&b| b == 0).map(|_| b'1'));
+    digits.reverse();
+    String::from_utf8(digits).unwrap()
+}
+
+/// The BIP-32 master extended private key of a seed
+fn master_xprv(seed: &[u8]) -> String {
+    let mut engine = HmacEngine::<sha512::Hash>::new(b"Bitcoin seed");
+    engine.input(seed);
+    let master = Hmac::<sha512::Hash>::from_engine(engine);
+
+    let mut ser = vec![0x04, 0x88, 0xad, 0xe4]; // mainnet private
+    ser.extend_from_slice(&[0; 9]); // depth, parent fingerprint, child number
+    ser.extend_from_slice(&master[32..]); // chain code
+    ser.push(0);
+    ser.extend_from_slice(&master[..32]); // key
+    base58check(&ser)
+}
+
+fn parse(s: &str) -> Result<Codex32String, String> {
+    Codex32String::from_string(s.into()).map_err(|e| format!("failed to parse {}: {:?}", s, e))
+}
+
+fn run_valid(v: &Valid) -> Result<(), String> {
+    let c32 = parse(&v.string)?;
+    let parts = c32.parts();
+    check("hrp", v.hrp.as_deref(), parts.hrp())?;
+    check("threshold", v.threshold, parts.threshold())?;
+    check("id", v.id.as_deref(), parts.id())?;
+    if let Some(ref index) = v.share_index {
+        check("share index", Some(parse_fe(index)?), parts.share_index())?;
+    }
+    let data = parts.data();
+    if let Some(ref expected) = v.data {
+        check("data", Some(expected.to_lowercase()), hex(&data))?;
+    }
+    check("xprv", v.xprv.as_deref(), &master_xprv(&data))?;
+    Ok(())
+}
+
+fn run_invalid(v: &Invalid) -> Result<(), String> {
+    match Codex32String::from_string(v.string.clone()) {
+        Ok(_) => Err(format!("accepted invalid string {}", v.string)),
+        Err(e) => check("error", v.error.as_deref(), &error_name(&e)),
+    }
+}
+
+fn run_interpolation(v: &Interpolation) -> Result<(), String> {
+    let shares = v
+        .shares
+        .iter()
+        .map(|s| parse(s))
+        .collect::<Result<Vec<_>, _>>()?;
+    for (index, expected) in &v.derived {
+        let target = parse_fe(index)?;
+        let derived = Codex32String::interpolate_at(&shares, target)
+            .map_err(|e| format!("failed to derive share {}: {:?}", index, e))?;
+        ensure!(
+            derived.expose_secret() == expected,
+            "share {}: expected {}, got {}",
+            index,
+            expected,
+            derived.expose_secret()
+        );
+    }
+    Ok(())
+}
+
+fn run_seed(v: &Seed) -> Result<(), String> {
+    let seed = from_hex(&v.seed)?;
+    let index = parse_fe(&v.share_index)?;
+    let c32 = Codex32String::from_seed(&v.hrp, v.threshold, &v.id, index, &seed)
+        .map_err(|e| format!("failed to encode seed: {:?}", e))?;
+    check("string", Some(v.string.as_str()), c32.expose_secret())?;
+    ensure!(
+        c32.parts().data()[..] == seed[..],
+        "seed did not survive a round trip"
+    );
+    check("xprv", v.xprv.as_deref(), &master_xprv(&seed))?;
+    Ok(())
+}
+
+/// Runs every vector in a file, returning a description of each failure
+fn run_file(path: &Path) -> Vec<String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => return vec![format!("{}: {}", path.display(), e)],
+    };
+    // TOML files are converted to JSON values, so both are read alike
+    let file: Result<Value, String> = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(&contents).map_err(|e| e.to_string()),
+        Some("toml") => toml::from_str::<toml::Value>(&contents)
+            .map_err(|e| e.to_string())
+            .and_then(|v| serde_json::to_value(v).map_err(|e| e.to_string())),
+        _ => Err("unknown file type, expected .json or .toml".into()),
+    };
+    let file = match file.and_then(VectorFile::from_value) {
+        Ok(file) => file,
+        Err(e) => return vec![format!("{}: {}", path.display(), e)],
+    };
+
+    let mut failures = vec![];
+    let mut record = |section: &str, i: usize, comment: &Option<String>, result| {
+        if let Err(e) = result {
+            let comment = match comment {
+                Some(comment) => format!(" ({})", comment),
+                None => String::new(),
+            };
+            failures.push(format!(
+                "{}: {}[{}]{}: {}",
+                path.display(),
+                section,
+                i,
+                comment,
+                e
+            ));
+        }
+    };
+    for (i, v) in file.valid.iter().enumerate() {
+        record("valid", i, &v.comment, run_valid(v));
+    }
+    for (i, v) in file.invalid.iter().enumerate() {
+        record("invalid", i, &v.comment, run_invalid(v));
+    }
+    for (i, v) in file.interpolations.iter().enumerate() {
+        record("interpolations", i, &v.comment, run_interpolation(v));
+    }
+    for (i, v) in file.seeds.iter().enumerate() {
+        record("seeds", i, &v.comment, run_seed(v));
+    }
+    failures
+}
+
+#[test]
+fn vector_files() {
+    let data_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("data");
+    let mut paths: Vec<PathBuf> = fs::read_dir(&data_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let ext = path.extension().and_then(|ext| ext.to_str());
+            ext == Some("json") || ext == Some("toml")
+        })
+        .collect();
+    paths.sort();
+    if let Ok(extra) = env::var("CODEX32_VECTORS") {
+        paths.extend(
+            extra
+                .split(':')
+                .filter(|s| !s.is_empty())
+                .map(PathBuf::from),
+        );
+    }
+    assert!(!paths.is_empty());
+
+    let failures: Vec<String> = paths.iter().flat_map(|path| run_file(path)).collect();
+    assert!(
+        failures.is_empty(),
+        "{} vectors failed:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn master_key() {
+    // BIP-32 test vector 1
+    assert_eq!(
+        master_xprv(&from_hex("000102030405060708090a0b0c0d0e0f").unwrap()),
+        "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi",
+    );
+}